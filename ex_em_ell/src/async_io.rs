@@ -0,0 +1,236 @@
+//! Reading and writing documents over [tokio](https://crates.io/crates/tokio) streams, which is
+//! enabled by the `tokio` feature
+//!
+//! These work like the functions of the same name at the root of the crate, with the same
+//! [`FromXmlDocument`] and [`ToXmlDocument`] implementations. Those implementations are
+//! synchronous, so they are run on a blocking thread, and the document is passed between that
+//! thread and the stream a little at a time:
+//!
+//! - A document is parsed with the [`QuickXmlAsyncSource`] as each chunk of it arrives, and each
+//!   event is handed to the [`XmlEventReader`](crate::XmlEventReader) as soon as it is parsed, so
+//!   the limits of the [`ReadOptions`] are checked before the rest of the document is read.
+//! - A document is written to the stream as it is serialized, on the task that writes it, so only
+//!   a chunk of it is held in memory at once. This needs the multi-threaded runtime, as the task
+//!   blocks its thread with [`task::block_in_place`] while serializing, so the stream has to be
+//!   read by another task. On the current-thread runtime the whole document is serialized in
+//!   memory before it is written instead.
+
+use std::io::{self, BufWriter, Write};
+
+use tokio::{
+    io::{AsyncRead, AsyncWrite, AsyncWriteExt},
+    runtime::{Handle, RuntimeFlavor},
+    sync::mpsc,
+    task,
+};
+
+use crate::{
+    backend::{quick_xml::QuickXmlAsyncSource, XmlEventSource, XmlSourceError},
+    errors::{TextPosition, XmlReadError, XmlWriteError},
+    events::XmlEvent,
+    FromXmlDocument, ReadOptions, ToXmlDocument, WriteOptions,
+};
+
+/// How many events the source may read ahead of the value being built from them
+const EVENTS_READ_AHEAD: usize = 64;
+
+pub async fn from_reader<T: FromXmlDocument + Send + 'static, R: AsyncRead + Unpin>(
+    reader: R,
+) -> Result<T, XmlReadError> {
+    from_reader_with_options(reader, ReadOptions::default()).await
+}
+
+pub async fn from_reader_with_options<T: FromXmlDocument + Send + 'static, R: AsyncRead + Unpin>(
+    reader: R,
+    options: ReadOptions,
+) -> Result<T, XmlReadError> {
+    let source = QuickXmlAsyncSource::new(reader, &options);
+    let (sender, receiver) = mpsc::channel(EVENTS_READ_AHEAD);
+    let mut read =
+        task::spawn_blocking(move || crate::from_source(ReceivedEvents::new(receiver), options));
+
+    // The events stop being sent as soon as the value has been read, or has failed to be, so
+    // that the rest of the document is not waited for
+    tokio::select! {
+        value = &mut read => return joined(value, to_read_error),
+        () = send_events(source, sender) => {}
+    }
+    joined(read.await, to_read_error)
+}
+
+/// Write `value` to `writer`, blocking the thread of this task while it is serialized on the
+/// multi-threaded runtime, as described in the [module documentation](self)
+pub async fn to_writer<W: AsyncWrite + Unpin, T: ToXmlDocument>(
+    writer: W,
+    value: &T,
+) -> Result<(), XmlWriteError> {
+    to_writer_with_options(writer, value, WriteOptions::default()).await
+}
+
+/// Write `value` to `writer` with indentation, blocking the thread of this task while it is
+/// serialized on the multi-threaded runtime, as described in the [module documentation](self)
+pub async fn to_writer_pretty<W: AsyncWrite + Unpin, T: ToXmlDocument>(
+    writer: W,
+    value: &T,
+) -> Result<(), XmlWriteError> {
+    to_writer_with_options(writer, value, WriteOptions::default().perform_indent(true)).await
+}
+
+/// Write `value` to `writer` with `options`, blocking the thread of this task while it is
+/// serialized on the multi-threaded runtime, as described in the [module documentation](self)
+pub async fn to_writer_with_options<W: AsyncWrite + Unpin, T: ToXmlDocument>(
+    mut writer: W,
+    value: &T,
+    options: WriteOptions,
+) -> Result<(), XmlWriteError> {
+    let handle = Handle::current();
+    match handle.runtime_flavor() {
+        RuntimeFlavor::MultiThread => task::block_in_place(|| {
+            let mut output = BufWriter::new(BlockingWriter {
+                writer: &mut writer,
+                handle: &handle,
+            });
+            crate::to_writer_with_options(&mut output, value, options)?;
+            output.flush().map_err(to_write_error)
+        })?,
+        // Nothing else could run while this task blocked the only thread, so the document is
+        // serialized before any of it is written
+        _ => {
+            let mut output = Vec::new();
+            crate::to_writer_with_options(&mut output, value, options)?;
+            writer.write_all(&output).await.map_err(to_write_error)?;
+        }
+    }
+    writer.flush().await.map_err(to_write_error)
+}
+
+/// Send the events of `source` to the [`ReceivedEvents`] that are being read, until the end of
+/// the document, the first error, or the events are no longer being received
+async fn send_events<R: AsyncRead + Unpin>(
+    mut source: QuickXmlAsyncSource<R>,
+    sender: mpsc::Sender<ReceivedEvent>,
+) {
+    let mut sent_doctype = false;
+    loop {
+        let event = source.next_event().await;
+        let is_last = matches!(event, Ok(XmlEvent::EndDocument) | Err(_));
+        let doctype = match source.doctype() {
+            Some(doctype) if !sent_doctype => {
+                sent_doctype = true;
+                Some(doctype.to_string())
+            }
+            _ => None,
+        };
+
+        let received = ReceivedEvent {
+            event,
+            position: source.position(),
+            doctype,
+        };
+        if sender.send(received).await.is_err() || is_last {
+            break;
+        }
+    }
+}
+
+/// The result of a blocking thread, which panics again if the thread panicked
+fn joined<T, E>(
+    result: Result<Result<T, E>, task::JoinError>,
+    to_error: fn(io::Error) -> E,
+) -> Result<T, E> {
+    match result {
+        Ok(result) => result,
+        Err(error) if error.is_panic() => std::panic::resume_unwind(error.into_panic()),
+        Err(error) => Err(to_error(io::Error::other(error))),
+    }
+}
+
+fn to_read_error(error: io::Error) -> XmlReadError {
+    XmlReadError::IoError {
+        error,
+        element: "document".to_string(),
+        position: None,
+        path: None,
+    }
+}
+
+fn to_write_error(error: io::Error) -> XmlWriteError {
+    XmlWriteError::IoError {
+        error,
+        element: "document".to_string(),
+    }
+}
+
+/// An event that has been read from a [`QuickXmlAsyncSource`], along with the position it was
+/// read at, and the DOCTYPE if it was read along with this event
+struct ReceivedEvent {
+    event: Result<XmlEvent, XmlSourceError>,
+    position: TextPosition,
+    doctype: Option<String>,
+}
+
+/// The events of a document that are being read from a [`QuickXmlAsyncSource`], which are
+/// received by the [`XmlEventReader`](crate::XmlEventReader) on a blocking thread
+///
+/// Reading stops at the first error, which is received in place of the event it was read at.
+struct ReceivedEvents {
+    receiver: mpsc::Receiver<ReceivedEvent>,
+    position: TextPosition,
+    doctype: Option<String>,
+}
+
+impl ReceivedEvents {
+    fn new(receiver: mpsc::Receiver<ReceivedEvent>) -> Self {
+        Self {
+            receiver,
+            position: TextPosition { line: 1, column: 1 },
+            doctype: None,
+        }
+    }
+}
+
+impl XmlEventSource for ReceivedEvents {
+    fn next_event(&mut self) -> Result<XmlEvent, XmlSourceError> {
+        match self.receiver.blocking_recv() {
+            Some(received) => {
+                self.position = received.position;
+                if received.doctype.is_some() {
+                    self.doctype = received.doctype;
+                }
+                received.event
+            }
+            // The events are only stopped early when the value is no longer being read
+            None => Err(XmlSourceError::Io {
+                error: io::Error::new(
+                    io::ErrorKind::BrokenPipe,
+                    "The events stopped before the end of the document",
+                ),
+                position: self.position,
+            }),
+        }
+    }
+
+    fn position(&self) -> TextPosition {
+        self.position
+    }
+
+    fn doctype(&self) -> Option<&str> {
+        self.doctype.as_deref()
+    }
+}
+
+/// Writes to an async stream from within [`task::block_in_place`], waiting for each write
+struct BlockingWriter<'w, W> {
+    writer: &'w mut W,
+    handle: &'w Handle,
+}
+
+impl<W: AsyncWrite + Unpin> Write for BlockingWriter<'_, W> {
+    fn write(&mut self, chunk: &[u8]) -> io::Result<usize> {
+        self.handle.block_on(self.writer.write(chunk))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.handle.block_on(self.writer.flush())
+    }
+}
@@ -0,0 +1,109 @@
+//! The parsers and writers that documents are read and written with
+//!
+//! Derived and hand-written readers and writers only see the crate's own
+//! [events](crate::events), so any parser can be used by implementing [`XmlEventSource`], and any
+//! writer by implementing [`XmlEventSink`]. The [`xml_rs`] backend is used by default, and a
+//! [quick-xml](https://crates.io/crates/quick-xml) backend is available with the `quick-xml` feature.
+
+#[cfg(feature = "quick-xml")]
+pub mod quick_xml;
+pub mod xml_rs;
+
+use std::borrow::Cow;
+
+use crate::{
+    errors::{ReadLimit, TextPosition},
+    events::{WriteEvent, XmlEvent},
+    options::XmlVersion,
+};
+
+/// A parser that reads a document as a series of [`XmlEvent`]s
+///
+/// The [`XmlEventReader`](crate::XmlEventReader) reads from a source, and tracks the path and
+/// limits of the document and the DOCTYPE policy on top of it.
+pub trait XmlEventSource {
+    /// Read the next event of the document
+    ///
+    /// Once this has returned an error, the rest of the document is not read.
+    fn next_event(&mut self) -> Result<XmlEvent, XmlSourceError>;
+
+    /// The position of the last event that was read
+    fn position(&self) -> TextPosition;
+
+    /// The text of the document's `<!DOCTYPE>`, once the source has read past it
+    ///
+    /// A source that does not report the DOCTYPE must not expand the entities it declares.
+    fn doctype(&self) -> Option<&str>;
+}
+
+/// A parser that reads a document from a buffer, and can lend out the text of its events
+///
+/// This is what [`FromXmlElementBorrowed`](crate::FromXmlElementBorrowed) types are read from.
+/// Reading events with [`next_event`](XmlEventSource::next_event) and
+/// [`next_borrowed_event`](Self::next_borrowed_event) moves through the same document.
+pub trait XmlBorrowedEventSource<'de>: XmlEventSource {
+    /// Read the next event of the document, borrowing its text from the document unless it had
+    /// to be decoded, e.g. because it contained entity references
+    fn next_borrowed_event(&mut self) -> Result<XmlEvent<Cow<'de, str>>, XmlSourceError>;
+}
+
+/// A writer that writes a document from a series of [`WriteEvent`]s
+pub trait XmlEventSink {
+    /// What the document is written to, e.g. a [`std::io::Write`]
+    type Inner;
+
+    /// Write the next event of the document
+    ///
+    /// Names and text have already been checked to be valid XML, but markup characters in text
+    /// must be escaped by the sink.
+    fn write_event(&mut self, event: WriteEvent<'_>) -> Result<(), XmlSinkError>;
+
+    /// The version of XML that the document is written as, which decides the characters that
+    /// its text may contain
+    fn xml_version(&self) -> XmlVersion {
+        XmlVersion::Version10
+    }
+
+    /// The encoding named in the document's declaration
+    ///
+    /// Documents are always written as UTF-8, so when another encoding is declared, names and
+    /// text are checked to be ASCII, which is written the same in either.
+    fn encoding(&self) -> &str {
+        "utf-8"
+    }
+
+    /// Finish writing and return what the document was written to
+    fn into_inner(self) -> Self::Inner;
+}
+
+/// An error from an [`XmlEventSource`]
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum XmlSourceError {
+    /// The document is not well-formed XML
+    Syntax {
+        error: Box<dyn std::error::Error + Send + Sync>,
+        position: TextPosition,
+    },
+    Io {
+        error: std::io::Error,
+        position: TextPosition,
+    },
+    /// The document exceeded a limit that the source enforces itself
+    LimitExceeded { limit: ReadLimit },
+}
+
+/// An error from an [`XmlEventSink`]
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum XmlSinkError {
+    /// The events do not form a document, e.g. an end tag without a start tag
+    Invalid(Box<dyn std::error::Error + Send + Sync>),
+    Io(std::io::Error),
+}
+
+impl From<std::io::Error> for XmlSinkError {
+    fn from(error: std::io::Error) -> Self {
+        XmlSinkError::Io(error)
+    }
+}
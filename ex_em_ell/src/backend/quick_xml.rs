@@ -0,0 +1,1102 @@
+//! The [quick-xml](https://crates.io/crates/quick-xml) backend, which is enabled by the
+//! `quick-xml` feature
+//!
+//! It reads and writes the same documents as the [`xml_rs`](super::xml_rs) backend, with the same
+//! events and formatting, so either can be used with the same [`FromXmlElement`] and
+//! [`ToXmlElement`] implementations.
+//!
+//! [`FromXmlElement`]: crate::FromXmlElement
+//! [`ToXmlElement`]: crate::ToXmlElement
+
+use std::{
+    borrow::Cow,
+    cell::Cell,
+    collections::{BTreeMap, HashMap, VecDeque},
+    io::{self, BufRead, Read, Write},
+    sync::Arc,
+};
+
+#[cfg(feature = "tokio")]
+use std::{
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use ::quick_xml::{
+    escape::{escape, partial_escape, resolve_predefined_entity},
+    events::{
+        attributes::Attribute, BytesCData, BytesDecl, BytesEnd, BytesPI, BytesStart, BytesText,
+        Event,
+    },
+    name::QName,
+    Reader, Writer,
+};
+
+#[cfg(feature = "tokio")]
+use ::tokio::io::{AsyncBufRead, AsyncRead, BufReader as AsyncBufReader, ReadBuf};
+
+use crate::{
+    backend::{XmlBorrowedEventSource, XmlEventSink, XmlEventSource, XmlSinkError, XmlSourceError},
+    doctype::internal_entities,
+    errors::{ReadLimit, TextPosition},
+    events::{Namespace, WriteEvent, XmlAttribute, XmlEvent, XmlName},
+    options::{DoctypePolicy, ReadOptions, WriteOptions, XmlVersion},
+};
+
+const XML_PREFIX: &str = "xml";
+const XML_URI: &str = "http://www.w3.org/XML/1998/namespace";
+const XMLNS_PREFIX: &str = "xmlns";
+const XMLNS_URI: &str = "http://www.w3.org/2000/xmlns/";
+
+/// The state shared by the quick-xml sources, which turns raw quick-xml events into the events
+/// of this crate
+///
+/// Text is borrowed for `'de` when it is a slice of `input` that did not need to be decoded.
+struct EventState<'de> {
+    /// The whole document, when it is read from memory
+    input: Option<&'de str>,
+    options: ReadOptions,
+    /// The internal entities declared by the DOCTYPE, when the policy allows expanding them
+    entities: HashMap<String, String>,
+    /// How many characters the declared entities have expanded to so far in the document, which
+    /// the budget of the [`DoctypePolicy`] applies to
+    expanded_length: Cell<usize>,
+    /// The namespaces in scope at each open element, starting with those in scope at the root
+    scopes: Vec<Namespace>,
+    /// The names of the open elements, as they were written in the document
+    open_elements: Vec<String>,
+    /// The text read since the last markup, with the position it started at
+    text: Option<(Cow<'de, str>, TextPosition)>,
+    pending: VecDeque<(XmlEvent<Cow<'de, str>>, TextPosition)>,
+    position: TextPosition,
+    doctype: Option<String>,
+    started_document: bool,
+}
+
+impl<'de> EventState<'de> {
+    fn new(input: Option<&'de str>, options: &ReadOptions) -> Self {
+        Self {
+            input,
+            options: options.clone(),
+            entities: HashMap::new(),
+            expanded_length: Cell::new(0),
+            scopes: vec![root_namespace()],
+            open_elements: Vec::new(),
+            text: None,
+            pending: VecDeque::new(),
+            position: TextPosition { line: 1, column: 1 },
+            doctype: None,
+            started_document: false,
+        }
+    }
+
+    /// Take the next event that is ready to be returned, if there is one
+    fn next_pending(&mut self) -> Option<XmlEvent<Cow<'de, str>>> {
+        let (event, position) = self.pending.pop_front()?;
+        self.position = position;
+        Some(event)
+    }
+
+    /// Keep `text` for `'de`, borrowing it from the input if it is a slice of it and copying it
+    /// otherwise
+    fn lend(&self, text: Cow<'_, str>) -> Cow<'de, str> {
+        match text {
+            Cow::Borrowed(slice) => match self.input.and_then(|input| reborrow(input, slice)) {
+                Some(slice) => Cow::Borrowed(slice),
+                None => Cow::Owned(slice.to_string()),
+            },
+            Cow::Owned(text) => Cow::Owned(text),
+        }
+    }
+
+    fn handle_event(
+        &mut self,
+        event: Event<'_>,
+        position: TextPosition,
+    ) -> Result<(), XmlSourceError> {
+        if !self.started_document {
+            self.started_document = true;
+            if let Event::Decl(declaration) = &event {
+                let event = start_document(declaration, position)?;
+                self.pending.push_back((event.into(), position));
+                return Ok(());
+            }
+            // Like xml-rs, a document without a declaration still starts with one
+            let event = XmlEvent::StartDocument {
+                version: XmlVersion::Version10,
+                encoding: "UTF-8".to_string(),
+                standalone: None,
+            };
+            self.pending.push_back((event, position));
+        }
+
+        match event {
+            Event::Text(text) => self.push_text(text.xml10_content(), position),
+            Event::GeneralRef(reference) => {
+                let mut resolved = String::new();
+                self.resolve_reference(&reference, &mut resolved, 0, position)?;
+                self.push_text(Cow::Owned(resolved), position)
+            }
+            Event::CData(cdata) if self.options.cdata_to_characters => {
+                self.push_text(cdata.xml10_content(), position)
+            }
+            Event::CData(cdata) => {
+                self.flush_text()?;
+                let event = XmlEvent::CData(self.lend(cdata.xml10_content()));
+                self.pending.push_back((event, position));
+                Ok(())
+            }
+            Event::Comment(_) if self.options.ignore_comments => Ok(()),
+            Event::Comment(comment) => {
+                self.flush_text()?;
+                let event = XmlEvent::Comment(self.lend(comment.xml10_content()));
+                self.pending.push_back((event, position));
+                Ok(())
+            }
+            Event::PI(instruction) => {
+                self.flush_text()?;
+                let data = instruction.content().trim_start();
+                let event = XmlEvent::ProcessingInstruction {
+                    name: instruction.target().to_string(),
+                    data: (!data.is_empty()).then(|| data.to_string()),
+                };
+                self.pending.push_back((event, position));
+                Ok(())
+            }
+            Event::DocType(doctype) => {
+                self.read_doctype(&doctype.xml10_content());
+                Ok(())
+            }
+            Event::Decl(_) => Err(syntax_error(
+                "Unexpected XML declaration after the start of the document",
+                position,
+            )),
+            Event::Start(start) => {
+                self.flush_text()?;
+                self.start_element(&start, position)
+            }
+            Event::Empty(start) => {
+                self.flush_text()?;
+                self.start_element(&start, position)?;
+                self.end_element(start.name().0, position)
+            }
+            Event::End(end) => {
+                self.flush_text()?;
+                self.end_element(end.name().0, position)
+            }
+            Event::Eof => {
+                self.flush_text()?;
+                if let Some(name) = self.open_elements.last() {
+                    return Err(syntax_error(
+                        format!("Unexpected end of the document, `{}` is not closed", name),
+                        position,
+                    ));
+                }
+                self.pending.push_back((XmlEvent::EndDocument, position));
+                Ok(())
+            }
+        }
+    }
+
+    /// Add to the text read since the last markup, which is only copied if it is split by
+    /// references or other markup
+    fn push_text(
+        &mut self,
+        text: Cow<'_, str>,
+        position: TextPosition,
+    ) -> Result<(), XmlSourceError> {
+        let buffered = match &mut self.text {
+            Some((buffered, _)) => {
+                buffered.to_mut().push_str(&text);
+                buffered
+            }
+            None => &mut self.text.insert((self.lend(text), position)).0,
+        };
+        // The reader checks the length of the text too, but stopping here keeps the buffer small
+        if buffered.len() > self.options.max_text_length {
+            return Err(XmlSourceError::LimitExceeded {
+                limit: ReadLimit::TextLength(self.options.max_text_length),
+            });
+        }
+        Ok(())
+    }
+
+    /// Turn the text read since the last markup into an event
+    fn flush_text(&mut self) -> Result<(), XmlSourceError> {
+        let Some((text, position)) = self.text.take() else {
+            return Ok(());
+        };
+
+        let is_whitespace = text.chars().all(char::is_whitespace);
+        if self.open_elements.is_empty() {
+            // Whitespace outside the root element is not reported, as with xml-rs
+            return if is_whitespace {
+                Ok(())
+            } else {
+                Err(syntax_error(
+                    "Unexpected characters outside the root element",
+                    position,
+                ))
+            };
+        }
+
+        // Whitespace is trimmed by the reader, which can join text split by comments first
+        let event = if is_whitespace {
+            XmlEvent::Whitespace(text)
+        } else {
+            XmlEvent::Characters(text)
+        };
+        self.pending.push_back((event, position));
+        Ok(())
+    }
+
+    fn start_element(
+        &mut self,
+        start: &BytesStart<'_>,
+        position: TextPosition,
+    ) -> Result<(), XmlSourceError> {
+        let mut namespace = self.scopes.last().cloned().unwrap_or_else(root_namespace);
+        let mut attributes = Vec::new();
+        for attribute in start.attributes() {
+            let attribute = attribute.map_err(|error| XmlSourceError::Syntax {
+                error: Box::new(error),
+                position,
+            })?;
+            let value = self.attribute_value(attribute.value, position)?;
+            match attribute.key.0.split_once(':') {
+                None if attribute.key.0 == XMLNS_PREFIX => {
+                    namespace.0.insert(String::new(), value.into_owned());
+                }
+                Some((XMLNS_PREFIX, prefix)) => {
+                    namespace.0.insert(prefix.to_string(), value.into_owned());
+                }
+                _ => attributes.push((attribute.key.0.to_string(), value)),
+            }
+        }
+
+        let name = resolve_name(start.name().0, &namespace, true, position)?;
+        let attributes = attributes
+            .into_iter()
+            .map(|(name, value)| {
+                Ok(XmlAttribute {
+                    name: resolve_name(&name, &namespace, false, position)?,
+                    value,
+                })
+            })
+            .collect::<Result<_, XmlSourceError>>()?;
+
+        self.open_elements.push(start.name().0.to_string());
+        self.scopes.push(namespace.clone());
+        let event = XmlEvent::StartElement {
+            name,
+            attributes,
+            namespace,
+        };
+        self.pending.push_back((event, position));
+        Ok(())
+    }
+
+    fn end_element(&mut self, name: &str, position: TextPosition) -> Result<(), XmlSourceError> {
+        let namespace = self.scopes.pop().unwrap_or_else(root_namespace);
+        self.open_elements.pop();
+        let event = XmlEvent::EndElement {
+            name: resolve_name(name, &namespace, true, position)?,
+        };
+        self.pending.push_back((event, position));
+        Ok(())
+    }
+
+    /// Resolve the references in an attribute's value, and normalize its whitespace
+    fn attribute_value(
+        &self,
+        raw: Cow<'_, str>,
+        position: TextPosition,
+    ) -> Result<Cow<'de, str>, XmlSourceError> {
+        if !raw.contains(['&', '\t', '\n', '\r']) {
+            return Ok(self.lend(raw));
+        }
+
+        let mut value = String::with_capacity(raw.len());
+        let mut rest: &str = &raw;
+        while let Some(index) = rest.find(['&', '\t', '\n', '\r']) {
+            value.push_str(&rest[..index]);
+            rest = &rest[index..];
+            if let Some(reference) = rest.strip_prefix('&') {
+                let (name, after) = reference.split_once(';').ok_or_else(|| {
+                    syntax_error("Unterminated reference in an attribute value", position)
+                })?;
+                self.resolve_reference(name, &mut value, 0, position)?;
+                rest = after;
+            } else {
+                // A line break of `\r\n` is a single space once normalized
+                value.push(' ');
+                rest = rest.strip_prefix("\r\n").unwrap_or_else(|| &rest[1..]);
+            }
+        }
+        value.push_str(rest);
+        Ok(Cow::Owned(value))
+    }
+
+    /// Append the text that the reference `&name;` stands for to `resolved`
+    ///
+    /// `depth` is how many entities the reference was found within.
+    fn resolve_reference(
+        &self,
+        name: &str,
+        resolved: &mut String,
+        depth: u8,
+        position: TextPosition,
+    ) -> Result<(), XmlSourceError> {
+        if let Some(code) = name.strip_prefix('#') {
+            let character = match code.strip_prefix('x') {
+                Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                None => code.parse().ok(),
+            }
+            .and_then(char::from_u32)
+            .filter(|character| *character != '\0')
+            .ok_or_else(|| {
+                syntax_error(
+                    format!("Invalid character reference `&{};`", name),
+                    position,
+                )
+            })?;
+            resolved.push(character);
+        } else if let Some(value) = resolve_predefined_entity(name) {
+            resolved.push_str(value);
+        } else if let Some(value) = self.options.extra_entities.get(name) {
+            resolved.push_str(value);
+        } else if let Some(value) = self.entities.get(name) {
+            self.expand_entity(value, resolved, depth + 1, position)?;
+        } else if self.options.replace_unknown_entity_references {
+            resolved.push(char::REPLACEMENT_CHARACTER);
+        } else {
+            return Err(syntax_error(
+                format!("Unexpected entity `&{};`", name),
+                position,
+            ));
+        }
+        Ok(())
+    }
+
+    /// Append the value of a declared entity to `resolved`, resolving the references within it
+    /// while staying within the budget of the [`DoctypePolicy`]
+    ///
+    /// Markup within the value is kept as text.
+    fn expand_entity(
+        &self,
+        value: &str,
+        resolved: &mut String,
+        depth: u8,
+        position: TextPosition,
+    ) -> Result<(), XmlSourceError> {
+        if depth > self.options.doctype.max_expansion_depth() {
+            return Err(self.expansion_limit_exceeded());
+        }
+
+        let mut rest = value;
+        while let Some(index) = rest.find('&') {
+            self.count_expansion(&rest[..index])?;
+            resolved.push_str(&rest[..index]);
+            let (name, after) = rest[index + 1..]
+                .split_once(';')
+                .ok_or_else(|| syntax_error("Unterminated reference in an entity", position))?;
+            match self.entities.get(name) {
+                Some(value) => self.expand_entity(value, resolved, depth + 1, position)?,
+                None => {
+                    let start = resolved.len();
+                    self.resolve_reference(name, resolved, depth, position)?;
+                    self.count_expansion(&resolved[start..])?;
+                }
+            }
+            rest = after;
+        }
+        self.count_expansion(rest)?;
+        resolved.push_str(rest);
+        Ok(())
+    }
+
+    /// Count `text` towards the characters that entities have expanded to in the document,
+    /// failing once there are more than the budget allows
+    fn count_expansion(&self, text: &str) -> Result<(), XmlSourceError> {
+        let length = self
+            .expanded_length
+            .get()
+            .saturating_add(text.chars().count());
+        self.expanded_length.set(length);
+        if length > self.options.doctype.max_expansion_length() {
+            return Err(self.expansion_limit_exceeded());
+        }
+        Ok(())
+    }
+
+    fn expansion_limit_exceeded(&self) -> XmlSourceError {
+        XmlSourceError::LimitExceeded {
+            limit: ReadLimit::EntityExpansion {
+                max_expansion_length: self.options.doctype.max_expansion_length(),
+                max_expansion_depth: self.options.doctype.max_expansion_depth(),
+            },
+        }
+    }
+
+    /// Keep the DOCTYPE for the reader to check, and the internal entities it declares if they
+    /// are allowed to be expanded
+    fn read_doctype(&mut self, content: &str) {
+        if let DoctypePolicy::AllowInternalEntities { .. } = self.options.doctype {
+            self.entities = internal_entities(content);
+        }
+        self.doctype = Some(format!("<!DOCTYPE {}>", content.trim()));
+    }
+}
+
+/// Reads events with a quick-xml [`Reader`]
+///
+/// Unlike xml-rs, quick-xml does not resolve namespaces or entities, or join text that is split
+/// by entity references, so the source does that itself.
+pub struct QuickXmlSource<R: BufRead> {
+    reader: Reader<PositionReader<R>>,
+    buffer: Vec<u8>,
+    state: EventState<'static>,
+}
+
+impl<R: BufRead> QuickXmlSource<R> {
+    pub fn new(source: R, options: &ReadOptions) -> Self {
+        let mut reader = Reader::from_reader(PositionReader::new(source));
+        configure(&mut reader);
+
+        Self {
+            reader,
+            buffer: Vec::new(),
+            state: EventState::new(None, options),
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader.into_inner().inner
+    }
+}
+
+impl<R: BufRead> XmlEventSource for QuickXmlSource<R> {
+    fn next_event(&mut self) -> Result<XmlEvent, XmlSourceError> {
+        loop {
+            if let Some(event) = self.state.next_pending() {
+                return Ok(event.into_owned());
+            }
+            let position = self.reader.get_ref().position();
+            self.buffer.clear();
+            match self.reader.read_event_into(&mut self.buffer) {
+                Ok(event) => self.state.handle_event(event, position)?,
+                Err(error) => return Err(to_source_error(error, position)),
+            }
+        }
+    }
+
+    fn position(&self) -> TextPosition {
+        self.state.position
+    }
+
+    fn doctype(&self) -> Option<&str> {
+        self.state.doctype.as_deref()
+    }
+}
+
+/// Reads events from a [`tokio::io::AsyncRead`] with a quick-xml [`Reader`], parsing the
+/// document as it arrives
+///
+/// This is read by [`async_io::from_reader`](crate::async_io::from_reader), and is enabled by the
+/// `tokio` feature.
+#[cfg(feature = "tokio")]
+pub struct QuickXmlAsyncSource<R: AsyncRead + Unpin> {
+    reader: Reader<PositionReader<AsyncBufReader<R>>>,
+    buffer: Vec<u8>,
+    state: EventState<'static>,
+}
+
+#[cfg(feature = "tokio")]
+impl<R: AsyncRead + Unpin> QuickXmlAsyncSource<R> {
+    pub fn new(source: R, options: &ReadOptions) -> Self {
+        let mut reader = Reader::from_reader(PositionReader::new(AsyncBufReader::new(source)));
+        configure(&mut reader);
+
+        Self {
+            reader,
+            buffer: Vec::new(),
+            state: EventState::new(None, options),
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader.into_inner().inner.into_inner()
+    }
+
+    /// Read the next event of the document, waiting for more of it to arrive if needed
+    ///
+    /// Once this has returned an error, the rest of the document is not read.
+    pub async fn next_event(&mut self) -> Result<XmlEvent, XmlSourceError> {
+        loop {
+            if let Some(event) = self.state.next_pending() {
+                return Ok(event.into_owned());
+            }
+            let position = self.reader.get_ref().position();
+            self.buffer.clear();
+            match self.reader.read_event_into_async(&mut self.buffer).await {
+                Ok(event) => self.state.handle_event(event, position)?,
+                Err(error) => return Err(to_source_error(error, position)),
+            }
+        }
+    }
+
+    /// The position of the last event that was read
+    pub fn position(&self) -> TextPosition {
+        self.state.position
+    }
+
+    /// The text of the document's `<!DOCTYPE>`, once the source has read past it
+    pub fn doctype(&self) -> Option<&str> {
+        self.state.doctype.as_deref()
+    }
+}
+
+/// Reads events from a document in memory with a quick-xml [`Reader`], borrowing text from it
+///
+/// Text is only copied when it had to be decoded, e.g. because it contains entity references or
+/// `\r\n` line breaks.
+pub struct QuickXmlStrSource<'de> {
+    reader: Reader<&'de [u8]>,
+    input: &'de str,
+    /// The byte offset that `line` and `column` have been counted up to
+    offset: usize,
+    line: u64,
+    column: u64,
+    state: EventState<'de>,
+}
+
+impl<'de> QuickXmlStrSource<'de> {
+    pub fn new(input: &'de str, options: &ReadOptions) -> Self {
+        let mut reader = Reader::from_str(input);
+        configure(&mut reader);
+
+        Self {
+            reader,
+            input,
+            offset: 0,
+            line: 1,
+            column: 1,
+            state: EventState::new(Some(input), options),
+        }
+    }
+
+    /// The position of the next raw event, which is counted on from the last one
+    fn raw_position(&mut self) -> TextPosition {
+        let offset = usize::try_from(self.reader.buffer_position()).unwrap_or(usize::MAX);
+        let offset = offset.min(self.input.len());
+        if offset > self.offset {
+            advance(
+                &mut self.line,
+                &mut self.column,
+                &self.input.as_bytes()[self.offset..offset],
+            );
+            self.offset = offset;
+        }
+        TextPosition {
+            line: self.line,
+            column: self.column,
+        }
+    }
+}
+
+impl XmlEventSource for QuickXmlStrSource<'_> {
+    fn next_event(&mut self) -> Result<XmlEvent, XmlSourceError> {
+        self.next_borrowed_event().map(XmlEvent::into_owned)
+    }
+
+    fn position(&self) -> TextPosition {
+        self.state.position
+    }
+
+    fn doctype(&self) -> Option<&str> {
+        self.state.doctype.as_deref()
+    }
+}
+
+impl<'de> XmlBorrowedEventSource<'de> for QuickXmlStrSource<'de> {
+    fn next_borrowed_event(&mut self) -> Result<XmlEvent<Cow<'de, str>>, XmlSourceError> {
+        loop {
+            if let Some(event) = self.state.next_pending() {
+                return Ok(event);
+            }
+            let position = self.raw_position();
+            match self.reader.read_event() {
+                Ok(event) => self.state.handle_event(event, position)?,
+                Err(error) => return Err(to_source_error(error, position)),
+            }
+        }
+    }
+}
+
+/// Writes events with a quick-xml [`Writer`]
+///
+/// The indentation and empty elements are written the same way as by the
+/// [`xml_rs`](super::xml_rs) backend.
+pub struct QuickXmlSink<W: Write> {
+    writer: Writer<W>,
+    options: WriteOptions,
+    /// What has been written within each open element, and before the root
+    indent_stack: Vec<IndentState>,
+    /// The names of the open elements
+    element_names: Vec<String>,
+    /// The namespaces declared on each open element
+    namespaces: Vec<BTreeMap<String, String>>,
+    /// A start tag that is kept back until it is known whether the element is empty
+    pending_start: Option<BytesStart<'static>>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum IndentState {
+    Nothing,
+    Markup,
+    Text,
+}
+
+impl<W: Write> QuickXmlSink<W> {
+    pub fn new(sink: W, options: &WriteOptions) -> Self {
+        let mut writer = Writer::new(sink);
+        writer.config_mut().add_space_before_slash_in_empty_elements = options.pad_self_closing;
+
+        Self {
+            writer,
+            options: options.clone(),
+            indent_stack: vec![IndentState::Nothing],
+            element_names: Vec::new(),
+            namespaces: Vec::new(),
+            pending_start: None,
+        }
+    }
+
+    fn indent_level(&self) -> usize {
+        self.element_names.len()
+    }
+
+    fn set_indent_state(&mut self, state: IndentState) {
+        if let Some(current) = self.indent_stack.last_mut() {
+            *current = state;
+        }
+    }
+
+    fn write_newline(&mut self, level: usize) -> io::Result<()> {
+        let writer = self.writer.get_mut();
+        writer.write_all(self.options.line_separator.as_bytes())?;
+        for _ in 0..level {
+            writer.write_all(self.options.indent_string.as_bytes())?;
+        }
+        Ok(())
+    }
+
+    fn before_markup(&mut self) -> io::Result<()> {
+        let state = self.indent_stack.last().copied();
+        if self.options.perform_indent
+            && state != Some(IndentState::Text)
+            && (self.indent_level() > 0 || state == Some(IndentState::Markup))
+        {
+            self.write_newline(self.indent_level())?;
+            if self.indent_level() > 0 && !self.options.indent_string.is_empty() {
+                self.set_indent_state(IndentState::Markup);
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the start tag that was kept back, now that the element has content
+    fn write_pending_start(&mut self) -> io::Result<()> {
+        match self.pending_start.take() {
+            Some(start) => self.writer.write_event(Event::Start(start)),
+            None => Ok(()),
+        }
+    }
+
+    fn start_element(
+        &mut self,
+        name: &str,
+        attributes: Vec<(&str, &str)>,
+        namespaces: Vec<(&str, &str)>,
+    ) -> io::Result<()> {
+        self.write_pending_start()?;
+        self.before_markup()?;
+        self.indent_stack.push(IndentState::Nothing);
+
+        // Like xml-rs, namespaces that are already declared with the same URI are not repeated
+        let mut declared = BTreeMap::new();
+        for (prefix, uri) in namespaces {
+            let in_scope = self
+                .namespaces
+                .iter()
+                .any(|scope| scope.get(prefix).map(String::as_str) == Some(uri));
+            if !in_scope && !declared.contains_key(prefix) {
+                declared.insert(prefix.to_string(), uri.to_string());
+            }
+        }
+
+        let mut start = BytesStart::new(name.to_string());
+        for (prefix, uri) in &declared {
+            let uri = escape_attribute(uri);
+            match prefix.as_str() {
+                XML_PREFIX | XMLNS_PREFIX => {}
+                "" if uri.is_empty() => {}
+                "" => start.push_attribute(attribute(XMLNS_PREFIX, uri)),
+                prefix => start.push_attribute(attribute(&format!("xmlns:{}", prefix), uri)),
+            }
+        }
+        for (name, value) in attributes {
+            start.push_attribute(attribute(name, escape_attribute(value)));
+        }
+
+        self.set_indent_state(IndentState::Markup);
+        self.element_names.push(name.to_string());
+        self.namespaces.push(declared);
+        if self.options.self_closing_empty_elements {
+            self.pending_start = Some(start);
+            Ok(())
+        } else {
+            self.writer.write_event(Event::Start(start))
+        }
+    }
+
+    fn end_element(&mut self) -> Result<(), XmlSinkError> {
+        let name = self.element_names.pop().ok_or_else(|| {
+            XmlSinkError::Invalid("Tried to end an element when none were open".into())
+        })?;
+        self.namespaces.pop();
+
+        let result = match self.pending_start.take() {
+            Some(start) => self.writer.write_event(Event::Empty(start)),
+            None => {
+                // The level is that of the element being ended, before it was removed
+                let state = self.indent_stack.last().copied();
+                if self.options.perform_indent && state == Some(IndentState::Markup) {
+                    self.write_newline(self.indent_level())?;
+                }
+                self.writer.write_event(Event::End(BytesEnd::new(name)))
+            }
+        };
+        self.indent_stack.pop();
+        self.set_indent_state(IndentState::Markup);
+        Ok(result?)
+    }
+}
+
+impl<W: Write> XmlEventSink for QuickXmlSink<W> {
+    type Inner = W;
+
+    fn write_event(&mut self, event: WriteEvent<'_>) -> Result<(), XmlSinkError> {
+        match event {
+            WriteEvent::StartDocument {
+                version,
+                encoding,
+                standalone,
+            } => {
+                self.before_markup()?;
+                let version = match version {
+                    XmlVersion::Version10 => "1.0",
+                    XmlVersion::Version11 => "1.1",
+                };
+                let standalone = standalone.map(|standalone| if standalone { "yes" } else { "no" });
+                self.writer.write_event(Event::Decl(BytesDecl::new(
+                    version,
+                    Some(encoding),
+                    standalone,
+                )))?;
+                self.set_indent_state(IndentState::Markup);
+            }
+            WriteEvent::ProcessingInstruction { name, data } => {
+                self.write_pending_start()?;
+                self.before_markup()?;
+                let content = match data {
+                    Some(data) => format!("{} {}", name, data),
+                    None => name.to_string(),
+                };
+                self.writer.write_event(Event::PI(BytesPI::new(content)))?;
+                self.set_indent_state(IndentState::Markup);
+            }
+            WriteEvent::StartElement {
+                name,
+                attributes,
+                namespaces,
+            } => self.start_element(name, attributes, namespaces)?,
+            WriteEvent::EndElement => self.end_element()?,
+            WriteEvent::CData(text) => {
+                self.write_pending_start()?;
+                // `]]>` can't be written within a CDATA section, so it is split across two
+                let mut rest = text;
+                while let Some(index) = rest.find("]]>") {
+                    self.writer
+                        .write_event(Event::CData(BytesCData::new(&rest[..index + 2])))?;
+                    rest = &rest[index + 2..];
+                }
+                self.writer
+                    .write_event(Event::CData(BytesCData::new(rest)))?;
+                self.set_indent_state(IndentState::Text);
+            }
+            WriteEvent::Comment(comment) => {
+                self.write_pending_start()?;
+                self.before_markup()?;
+                let start_padding = if comment.starts_with(char::is_whitespace) {
+                    ""
+                } else {
+                    " "
+                };
+                let end_padding = if comment.ends_with(char::is_whitespace) {
+                    ""
+                } else {
+                    " "
+                };
+                let comment = format!("{}{}{}", start_padding, comment, end_padding);
+                self.writer
+                    .write_event(Event::Comment(BytesText::from_escaped(comment)))?;
+                self.set_indent_state(IndentState::Markup);
+            }
+            WriteEvent::Characters(text) => {
+                self.write_pending_start()?;
+                self.writer
+                    .write_event(Event::Text(BytesText::from_escaped(partial_escape(text))))?;
+                self.set_indent_state(IndentState::Text);
+            }
+        }
+        Ok(())
+    }
+
+    fn xml_version(&self) -> XmlVersion {
+        self.options.xml_version
+    }
+
+    fn encoding(&self) -> &str {
+        self.options.declared_encoding()
+    }
+
+    fn into_inner(self) -> W {
+        self.writer.into_inner()
+    }
+}
+
+/// Check comments and end tags, as xml-rs always does
+fn configure<R>(reader: &mut Reader<R>) {
+    let config = reader.config_mut();
+    config.check_comments = true;
+    config.check_end_names = true;
+}
+
+/// The slice of `input` that `slice` points to, if it is one
+fn reborrow<'de>(input: &'de str, slice: &str) -> Option<&'de str> {
+    let offset = (slice.as_ptr() as usize).checked_sub(input.as_ptr() as usize)?;
+    input.get(offset..offset + slice.len())
+}
+
+/// Escape an attribute value the way xml-rs does, so that line breaks are kept
+fn escape_attribute(value: &str) -> String {
+    escape(value).replace('\n', "&#xA;")
+}
+
+/// An attribute whose value has already been escaped
+fn attribute(name: &str, escaped_value: String) -> Attribute<'_> {
+    Attribute {
+        key: QName(name),
+        value: Cow::Owned(escaped_value),
+    }
+}
+
+/// The namespaces that are in scope before any are declared
+fn root_namespace() -> Namespace {
+    Namespace(BTreeMap::from([
+        (String::new(), String::new()),
+        (XML_PREFIX.to_string(), XML_URI.to_string()),
+        (XMLNS_PREFIX.to_string(), XMLNS_URI.to_string()),
+    ]))
+}
+
+/// Resolve the prefix of `name` against the namespaces in scope
+///
+/// Only elements are in the default namespace when they don't have a prefix.
+fn resolve_name(
+    name: &str,
+    namespace: &Namespace,
+    is_element: bool,
+    position: TextPosition,
+) -> Result<XmlName, XmlSourceError> {
+    match name.split_once(':') {
+        Some((prefix, local_name)) => {
+            let uri = namespace.get(prefix).ok_or_else(|| {
+                syntax_error(format!("Element {} prefix is unbound", name), position)
+            })?;
+            Ok(XmlName {
+                local_name: local_name.to_string(),
+                namespace: Some(uri.to_string()),
+                prefix: Some(prefix.to_string()),
+            })
+        }
+        None => Ok(XmlName {
+            local_name: name.to_string(),
+            namespace: namespace
+                .get("")
+                .filter(|uri| is_element && !uri.is_empty())
+                .map(str::to_string),
+            prefix: None,
+        }),
+    }
+}
+
+fn start_document(
+    declaration: &BytesDecl<'_>,
+    position: TextPosition,
+) -> Result<XmlEvent, XmlSourceError> {
+    let to_syntax_error = |error: ::quick_xml::Error| XmlSourceError::Syntax {
+        error: Box::new(error),
+        position,
+    };
+    let version = match declaration.version().map_err(to_syntax_error)?.as_ref() {
+        "1.1" => XmlVersion::Version11,
+        _ => XmlVersion::Version10,
+    };
+    let encoding = match declaration.encoding() {
+        Some(encoding) => encoding
+            .map_err(|error| to_syntax_error(error.into()))?
+            .into_owned(),
+        None => "UTF-8".to_string(),
+    };
+    let standalone = match declaration.standalone() {
+        Some(standalone) => match standalone
+            .map_err(|error| to_syntax_error(error.into()))?
+            .as_ref()
+        {
+            "yes" => Some(true),
+            "no" => Some(false),
+            other => {
+                return Err(syntax_error(
+                    format!("Invalid standalone declaration `{}`", other),
+                    position,
+                ))
+            }
+        },
+        None => None,
+    };
+
+    Ok(XmlEvent::StartDocument {
+        version,
+        encoding,
+        standalone,
+    })
+}
+
+fn syntax_error(message: impl Into<String>, position: TextPosition) -> XmlSourceError {
+    XmlSourceError::Syntax {
+        error: message.into().into(),
+        position,
+    }
+}
+
+fn to_source_error(error: ::quick_xml::Error, position: TextPosition) -> XmlSourceError {
+    match error {
+        // The reader shares the underlying error, so it is only taken back if nothing else has it
+        ::quick_xml::Error::Io(error) => XmlSourceError::Io {
+            error: Arc::try_unwrap(error)
+                .unwrap_or_else(|error| io::Error::new(error.kind(), error.to_string())),
+            position,
+        },
+        error => XmlSourceError::Syntax {
+            error: Box::new(error),
+            position,
+        },
+    }
+}
+
+/// Counts the lines and columns of what quick-xml reads, which only tracks the byte offset
+struct PositionReader<R> {
+    inner: R,
+    line: u64,
+    column: u64,
+}
+
+impl<R> PositionReader<R> {
+    fn new(inner: R) -> Self {
+        Self {
+            inner,
+            line: 1,
+            column: 1,
+        }
+    }
+
+    fn position(&self) -> TextPosition {
+        TextPosition {
+            line: self.line,
+            column: self.column,
+        }
+    }
+}
+
+fn advance(line: &mut u64, column: &mut u64, bytes: &[u8]) {
+    for byte in bytes {
+        if *byte == b'\n' {
+            *line += 1;
+            *column = 1;
+        } else if byte & 0b1100_0000 != 0b1000_0000 {
+            // Bytes that continue a UTF-8 character are not counted as a column of their own
+            *column += 1;
+        }
+    }
+}
+
+impl<R: Read> Read for PositionReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let count = self.inner.read(buf)?;
+        advance(&mut self.line, &mut self.column, &buf[..count]);
+        Ok(count)
+    }
+}
+
+impl<R: BufRead> BufRead for PositionReader<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amount: usize) {
+        if amount > 0 {
+            // The buffer is already filled, so this only looks at what is being consumed
+            if let Ok(buffer) = self.inner.fill_buf() {
+                let consumed = &buffer[..amount.min(buffer.len())];
+                advance(&mut self.line, &mut self.column, consumed);
+            }
+        }
+        self.inner.consume(amount)
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<R: AsyncRead + Unpin> AsyncRead for PositionReader<AsyncBufReader<R>> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let filled = buf.filled().len();
+        ready!(Pin::new(&mut this.inner).poll_read(cx, buf))?;
+        advance(&mut this.line, &mut this.column, &buf.filled()[filled..]);
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<R: AsyncRead + Unpin> AsyncBufRead for PositionReader<AsyncBufReader<R>> {
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+        Pin::new(&mut self.get_mut().inner).poll_fill_buf(cx)
+    }
+
+    fn consume(self: Pin<&mut Self>, amount: usize) {
+        let this = self.get_mut();
+        // What is being consumed was filled by the last poll, so it is still buffered
+        let buffer = this.inner.buffer();
+        advance(
+            &mut this.line,
+            &mut this.column,
+            &buffer[..amount.min(buffer.len())],
+        );
+        Pin::new(&mut this.inner).consume(amount)
+    }
+}
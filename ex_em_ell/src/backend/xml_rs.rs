@@ -0,0 +1,479 @@
+//! The [xml-rs](https://crates.io/crates/xml-rs) backend, which is used by default
+
+use std::{
+    collections::HashMap,
+    io::{self, Read, Write},
+};
+
+use xml::{common, common::Position, reader, writer, EmitterConfig, EventReader, EventWriter};
+
+use crate::{
+    backend::{XmlEventSink, XmlEventSource, XmlSinkError, XmlSourceError},
+    doctype::{expansion_lengths, internal_entities},
+    errors::{ReadLimit, TextPosition},
+    events::{Namespace, WriteEvent, XmlAttribute, XmlEvent, XmlName},
+    options::{DoctypePolicy, ReadOptions, WriteOptions, XmlVersion},
+};
+
+/// The message xml-rs gives when a document exceeds the sizes it was configured with
+///
+/// xml-rs does not expose the kind of its errors, so this is the only way to tell them apart.
+/// Every size it is configured with comes from `max_text_length`, so this always means that a
+/// piece of text was too long.
+const PARSER_LIMIT_MESSAGE: &str =
+    "This document is larger/more complex than allowed by the parser's configuration";
+
+/// Reads events with an xml-rs [`EventReader`]
+pub struct XmlRsSource<R: Read> {
+    event_reader: EventReader<ReferenceCounter<R>>,
+    max_text_length: usize,
+    doctype: DoctypePolicy,
+    /// How many characters each entity declared by the DOCTYPE expands to, once they have been
+    /// checked against the expansion budget
+    expansion_lengths: Option<HashMap<String, usize>>,
+    /// How many characters the references read so far expand to in total
+    expanded_length: usize,
+}
+
+impl<R: Read> XmlRsSource<R> {
+    pub fn new(source: R, options: &ReadOptions) -> Self {
+        Self {
+            event_reader: EventReader::new_with_config(
+                ReferenceCounter::new(source, options),
+                parser_config(options),
+            ),
+            max_text_length: options.max_text_length,
+            doctype: options.doctype,
+            expansion_lengths: None,
+            expanded_length: 0,
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.event_reader.into_inner().inner
+    }
+
+    fn to_source_error(&self, error: reader::Error) -> XmlSourceError {
+        if error.msg() == PARSER_LIMIT_MESSAGE {
+            return XmlSourceError::LimitExceeded {
+                limit: ReadLimit::TextLength(self.max_text_length),
+            };
+        }
+        let position = error.position().into();
+        match error.kind() {
+            // The reader only lends out the underlying error, so it is wrapped with its kind kept
+            reader::ErrorKind::Io(io_error) => XmlSourceError::Io {
+                error: std::io::Error::new(io_error.kind(), error),
+                position,
+            },
+            _ => XmlSourceError::Syntax {
+                error: Box::new(error),
+                position,
+            },
+        }
+    }
+
+    /// Check the entities expanded so far against the expansion budget
+    ///
+    /// The entities of the DOCTYPE are checked with the root element, which follows the DOCTYPE,
+    /// so before any entities are expanded in the content of the document. xml-rs expands the
+    /// references itself, so the references it has read since are counted instead.
+    fn check_entities(&mut self) -> Result<(), XmlSourceError> {
+        let DoctypePolicy::AllowInternalEntities {
+            max_expansion_length,
+            max_expansion_depth,
+        } = self.doctype
+        else {
+            return Ok(());
+        };
+        let limit_exceeded = XmlSourceError::LimitExceeded {
+            limit: ReadLimit::EntityExpansion {
+                max_expansion_length,
+                max_expansion_depth,
+            },
+        };
+
+        if self.expansion_lengths.is_none() {
+            let entities = self
+                .event_reader
+                .doctype()
+                .map(internal_entities)
+                .unwrap_or_default();
+            self.expansion_lengths =
+                expansion_lengths(&entities, max_expansion_length, max_expansion_depth);
+            if self.expansion_lengths.is_none() {
+                return Err(limit_exceeded);
+            }
+        }
+        let Some(lengths) = &self.expansion_lengths else {
+            return Ok(());
+        };
+
+        let references = std::mem::take(&mut self.event_reader.source_mut().references);
+        for (name, count) in references {
+            let length = lengths.get(&name).copied().unwrap_or(0);
+            self.expanded_length = self
+                .expanded_length
+                .saturating_add(length.saturating_mul(count));
+        }
+        if self.expanded_length > max_expansion_length {
+            return Err(limit_exceeded);
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> XmlEventSource for XmlRsSource<R> {
+    fn next_event(&mut self) -> Result<XmlEvent, XmlSourceError> {
+        match self.event_reader.next() {
+            Ok(event) => {
+                if self.expansion_lengths.is_some()
+                    || matches!(event, reader::XmlEvent::StartElement { .. })
+                {
+                    self.check_entities()?;
+                }
+                Ok(to_event(event))
+            }
+            Err(error) => Err(self.to_source_error(error)),
+        }
+    }
+
+    fn position(&self) -> TextPosition {
+        self.event_reader.position().into()
+    }
+
+    fn doctype(&self) -> Option<&str> {
+        self.event_reader.doctype()
+    }
+}
+
+/// Writes events with an xml-rs [`EventWriter`]
+pub struct XmlRsSink<W: Write> {
+    event_writer: EventWriter<W>,
+    xml_version: XmlVersion,
+    encoding: String,
+}
+
+impl<W: Write> XmlRsSink<W> {
+    pub fn new(sink: W, options: &WriteOptions) -> Self {
+        Self {
+            event_writer: EventWriter::new_with_config(sink, emitter_config(options)),
+            xml_version: options.xml_version,
+            encoding: options.declared_encoding().to_string(),
+        }
+    }
+}
+
+impl<W: Write> XmlEventSink for XmlRsSink<W> {
+    type Inner = W;
+
+    fn write_event(&mut self, event: WriteEvent<'_>) -> Result<(), XmlSinkError> {
+        let event = match event {
+            WriteEvent::StartDocument {
+                version,
+                encoding,
+                standalone,
+            } => writer::XmlEvent::StartDocument {
+                version: version.into(),
+                encoding: Some(encoding),
+                standalone,
+            },
+            WriteEvent::ProcessingInstruction { name, data } => {
+                writer::XmlEvent::ProcessingInstruction { name, data }
+            }
+            WriteEvent::StartElement {
+                name,
+                attributes,
+                namespaces,
+            } => {
+                let mut builder = writer::XmlEvent::start_element(name);
+                for (prefix, uri) in namespaces {
+                    builder = if prefix.is_empty() {
+                        builder.default_ns(uri)
+                    } else {
+                        builder.ns(prefix, uri)
+                    };
+                }
+                for (name, value) in attributes {
+                    builder = builder.attr(name, value);
+                }
+                builder.into()
+            }
+            WriteEvent::EndElement => writer::XmlEvent::end_element().into(),
+            WriteEvent::CData(text) => writer::XmlEvent::CData(text),
+            WriteEvent::Comment(comment) => writer::XmlEvent::Comment(comment),
+            WriteEvent::Characters(text) => writer::XmlEvent::Characters(text),
+        };
+
+        self.event_writer.write(event).map_err(|error| match error {
+            writer::Error::Io(error) => XmlSinkError::Io(error),
+            error => XmlSinkError::Invalid(Box::new(error)),
+        })
+    }
+
+    fn xml_version(&self) -> XmlVersion {
+        self.xml_version
+    }
+
+    fn encoding(&self) -> &str {
+        &self.encoding
+    }
+
+    fn into_inner(self) -> W {
+        self.event_writer.into_inner()
+    }
+}
+
+/// Counts the references to entities in the content of a document as xml-rs reads it, so that
+/// the characters they expand to can be counted towards the expansion budget
+///
+/// The text of comments, CDATA sections, processing instructions and the DOCTYPE is skipped, as
+/// references within them are not expanded. Character references and the predefined entities are
+/// not counted.
+struct ReferenceCounter<R> {
+    inner: R,
+    /// Whether references are counted at all, which is only needed when entities may be declared
+    enabled: bool,
+    max_name_length: usize,
+    state: ScanState,
+    /// The number of times each entity has been referred to since they were last taken
+    references: HashMap<String, usize>,
+}
+
+/// Where a [`ReferenceCounter`] is within the document
+enum ScanState {
+    Text,
+    /// After `<`
+    Markup,
+    /// After `<!`, with what has been read since, until it is known what it starts
+    Declaration(Vec<u8>),
+    /// Within a comment, with how many `-` have just been read
+    Comment(u8),
+    /// Within a CDATA section, with how many `]` have just been read
+    CData(u8),
+    /// Within a processing instruction, with whether `?` has just been read
+    Instruction(bool),
+    /// Within the DOCTYPE, with how many `[` are open and the quote of the literal it is in
+    Doctype(usize, Option<u8>),
+    /// After `&`, with the name read since
+    Reference(Vec<u8>),
+}
+
+impl<R> ReferenceCounter<R> {
+    fn new(inner: R, options: &ReadOptions) -> Self {
+        Self {
+            inner,
+            enabled: matches!(options.doctype, DoctypePolicy::AllowInternalEntities { .. }),
+            max_name_length: options.max_text_length,
+            state: ScanState::Text,
+            references: HashMap::new(),
+        }
+    }
+
+    fn scan(&mut self, byte: u8) {
+        self.state = match std::mem::replace(&mut self.state, ScanState::Text) {
+            ScanState::Text | ScanState::Markup if byte == b'&' => ScanState::Reference(Vec::new()),
+            ScanState::Text if byte == b'<' => ScanState::Markup,
+            ScanState::Text => ScanState::Text,
+            ScanState::Markup if byte == b'!' => ScanState::Declaration(Vec::new()),
+            ScanState::Markup if byte == b'?' => ScanState::Instruction(false),
+            // References in attribute values are counted like those in text
+            ScanState::Markup if byte == b'>' => ScanState::Text,
+            ScanState::Markup => ScanState::Markup,
+            ScanState::Declaration(mut start) => {
+                start.push(byte);
+                if start == b"--" {
+                    ScanState::Comment(0)
+                } else if start == b"[CDATA[" {
+                    ScanState::CData(0)
+                } else if start == b"DOCTYPE" {
+                    ScanState::Doctype(0, None)
+                } else if b"--".starts_with(&start)
+                    || b"[CDATA[".starts_with(&start)
+                    || b"DOCTYPE".starts_with(&start)
+                {
+                    ScanState::Declaration(start)
+                } else {
+                    // Anything else is not well-formed, which is left for xml-rs to report
+                    ScanState::Markup
+                }
+            }
+            ScanState::Comment(dashes) => match byte {
+                b'-' => ScanState::Comment(dashes.saturating_add(1)),
+                b'>' if dashes >= 2 => ScanState::Text,
+                _ => ScanState::Comment(0),
+            },
+            ScanState::CData(brackets) => match byte {
+                b']' => ScanState::CData(brackets.saturating_add(1)),
+                b'>' if brackets >= 2 => ScanState::Text,
+                _ => ScanState::CData(0),
+            },
+            ScanState::Instruction(question) => match byte {
+                b'>' if question => ScanState::Text,
+                _ => ScanState::Instruction(byte == b'?'),
+            },
+            ScanState::Doctype(depth, Some(quote)) if byte == quote => {
+                ScanState::Doctype(depth, None)
+            }
+            ScanState::Doctype(depth, Some(quote)) => ScanState::Doctype(depth, Some(quote)),
+            ScanState::Doctype(depth, None) => match byte {
+                b'"' | b'\'' => ScanState::Doctype(depth, Some(byte)),
+                b'[' => ScanState::Doctype(depth + 1, None),
+                b']' => ScanState::Doctype(depth.saturating_sub(1), None),
+                b'>' if depth == 0 => ScanState::Text,
+                _ => ScanState::Doctype(depth, None),
+            },
+            ScanState::Reference(name) if byte == b';' => {
+                self.count_reference(&name);
+                ScanState::Text
+            }
+            // A reference that is not well-formed is left for xml-rs to report
+            ScanState::Reference(_)
+                if byte.is_ascii_whitespace() || matches!(byte, b'<' | b'&') =>
+            {
+                ScanState::Text
+            }
+            ScanState::Reference(mut name) => {
+                if name.len() < self.max_name_length {
+                    name.push(byte);
+                }
+                ScanState::Reference(name)
+            }
+        };
+    }
+
+    fn count_reference(&mut self, name: &[u8]) {
+        let Ok(name) = std::str::from_utf8(name) else {
+            return;
+        };
+        if name.starts_with('#') || matches!(name, "lt" | "gt" | "amp" | "apos" | "quot") {
+            return;
+        }
+        *self.references.entry(name.to_string()).or_default() += 1;
+    }
+}
+
+impl<R: Read> Read for ReferenceCounter<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let length = self.inner.read(buf)?;
+        if self.enabled {
+            for &byte in &buf[..length] {
+                self.scan(byte);
+            }
+        }
+        Ok(length)
+    }
+}
+
+fn parser_config(options: &ReadOptions) -> reader::ParserConfig2 {
+    // The reader checks the limits itself so that it can report them clearly, but the parser
+    // is given slightly larger limits on the length of text and names too, so that it never
+    // buffers much more than allowed. The number of attributes is only checked by the reader,
+    // once the start tag has been read, so that a limit from the parser is always one on text.
+    // Whitespace is trimmed by the reader, which can join text split by comments first.
+    reader::ParserConfig {
+        trim_whitespace: false,
+        cdata_to_characters: options.cdata_to_characters,
+        ignore_comments: options.ignore_comments,
+        extra_entities: options.extra_entities.clone(),
+        replace_unknown_entity_references: options.replace_unknown_entity_references,
+        ..reader::ParserConfig::new()
+    }
+    .max_attributes(usize::MAX)
+    .max_name_length(options.max_text_length.saturating_add(1))
+    .max_attribute_length(options.max_text_length.saturating_add(1))
+    .max_data_length(options.max_text_length.saturating_add(1))
+    // The entities are checked against the budget before any are expanded, and the references to
+    // them are counted as they are read, so xml-rs is only given a backstop. It counts every entity expanded within one reference as a level of
+    // depth, so its depth is left as high as it can go without overflowing.
+    .max_entity_expansion_length(options.doctype.max_expansion_length().saturating_add(1))
+    .max_entity_expansion_depth(u8::MAX - 1)
+}
+
+fn emitter_config(options: &WriteOptions) -> EmitterConfig {
+    EmitterConfig {
+        perform_indent: options.perform_indent,
+        indent_string: options.indent_string.clone().into(),
+        line_separator: options.line_separator.clone().into(),
+        // The declaration is written explicitly, so that its contents can be configured
+        write_document_declaration: false,
+        normalize_empty_elements: options.self_closing_empty_elements,
+        pad_self_closing: options.pad_self_closing,
+        ..EmitterConfig::new()
+    }
+}
+
+fn to_event(event: reader::XmlEvent) -> XmlEvent {
+    match event {
+        reader::XmlEvent::StartDocument {
+            version,
+            encoding,
+            standalone,
+        } => XmlEvent::StartDocument {
+            version: version.into(),
+            encoding,
+            standalone,
+        },
+        reader::XmlEvent::EndDocument => XmlEvent::EndDocument,
+        reader::XmlEvent::ProcessingInstruction { name, data } => {
+            XmlEvent::ProcessingInstruction { name, data }
+        }
+        reader::XmlEvent::StartElement {
+            name,
+            attributes,
+            namespace,
+        } => XmlEvent::StartElement {
+            name: to_name(name),
+            attributes: attributes
+                .into_iter()
+                .map(|attribute| XmlAttribute {
+                    name: to_name(attribute.name),
+                    value: attribute.value,
+                })
+                .collect(),
+            namespace: Namespace(namespace.0),
+        },
+        reader::XmlEvent::EndElement { name } => XmlEvent::EndElement {
+            name: to_name(name),
+        },
+        reader::XmlEvent::CData(text) => XmlEvent::CData(text),
+        reader::XmlEvent::Comment(comment) => XmlEvent::Comment(comment),
+        reader::XmlEvent::Characters(text) => XmlEvent::Characters(text),
+        reader::XmlEvent::Whitespace(text) => XmlEvent::Whitespace(text),
+    }
+}
+
+fn to_name(name: xml::name::OwnedName) -> XmlName {
+    XmlName {
+        local_name: name.local_name,
+        namespace: name.namespace,
+        prefix: name.prefix,
+    }
+}
+
+impl From<XmlVersion> for common::XmlVersion {
+    fn from(version: XmlVersion) -> Self {
+        match version {
+            XmlVersion::Version10 => common::XmlVersion::Version10,
+            XmlVersion::Version11 => common::XmlVersion::Version11,
+        }
+    }
+}
+
+impl From<common::XmlVersion> for XmlVersion {
+    fn from(version: common::XmlVersion) -> Self {
+        match version {
+            common::XmlVersion::Version10 => XmlVersion::Version10,
+            common::XmlVersion::Version11 => XmlVersion::Version11,
+        }
+    }
+}
+
+impl From<common::TextPosition> for TextPosition {
+    fn from(position: common::TextPosition) -> Self {
+        Self {
+            line: position.row + 1,
+            column: position.column + 1,
+        }
+    }
+}
@@ -0,0 +1,172 @@
+//! Reading the entity declarations of a `<!DOCTYPE>`, which both backends and the reader need
+
+use std::collections::HashMap;
+
+/// An `<!ENTITY>` declaration in a DOCTYPE
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct EntityDeclaration<'d> {
+    pub(crate) name: &'d str,
+    /// Whether this is a parameter entity, i.e. `<!ENTITY % name ...>`, which can only be used
+    /// within the DOCTYPE
+    pub(crate) parameter: bool,
+    /// The value of an internal entity, or `None` for an external entity, which has a `SYSTEM`
+    /// or `PUBLIC` identifier instead
+    pub(crate) value: Option<&'d str>,
+}
+
+/// The entities declared in `doctype`, in the order they are declared
+///
+/// Comments, processing instructions and quoted literals are skipped, so that text within them
+/// that looks like a declaration is not mistaken for one.
+pub(crate) fn entity_declarations(doctype: &str) -> Vec<EntityDeclaration<'_>> {
+    let mut declarations = Vec::new();
+    let mut rest = doctype;
+    while let Some(c) = rest.chars().next() {
+        rest = if let Some(comment) = rest.strip_prefix("<!--") {
+            skip_past(comment, "-->")
+        } else if let Some(instruction) = rest.strip_prefix("<?") {
+            skip_past(instruction, "?>")
+        } else if let Some(declaration) = rest
+            .strip_prefix("<!ENTITY")
+            .filter(|declaration| declaration.starts_with(is_xml_whitespace))
+        {
+            let (entity, after) = entity_declaration(declaration);
+            declarations.extend(entity);
+            after
+        } else if c == '"' || c == '\'' {
+            skip_past(&rest[1..], if c == '"' { "\"" } else { "'" })
+        } else {
+            &rest[c.len_utf8()..]
+        };
+    }
+    declarations
+}
+
+/// Read the declaration after `<!ENTITY`, returning it along with the rest of the DOCTYPE from
+/// the end of its value or name
+fn entity_declaration(declaration: &str) -> (Option<EntityDeclaration<'_>>, &str) {
+    let declaration = declaration.trim_start_matches(is_xml_whitespace);
+    let (parameter, declaration) = match declaration.strip_prefix('%') {
+        Some(rest) => (true, rest.trim_start_matches(is_xml_whitespace)),
+        None => (false, declaration),
+    };
+    let name_length = declaration
+        .find(|c: char| is_xml_whitespace(c) || matches!(c, '"' | '\'' | '>'))
+        .unwrap_or(declaration.len());
+    let (name, rest) = declaration.split_at(name_length);
+    if name.is_empty() {
+        return (None, rest);
+    }
+
+    let rest = rest.trim_start_matches(is_xml_whitespace);
+    let quote = rest.chars().next().filter(|c| matches!(c, '"' | '\''));
+    let (value, rest) = match quote {
+        Some(quote) => match rest[1..].split_once(quote) {
+            Some((value, after)) => (Some(value), after),
+            // An unterminated value is left for the parser to report
+            None => return (None, ""),
+        },
+        None => (None, rest),
+    };
+    let declaration = EntityDeclaration {
+        name,
+        parameter,
+        value,
+    };
+    (Some(declaration), rest)
+}
+
+fn skip_past<'d>(text: &'d str, end: &str) -> &'d str {
+    text.find(end)
+        .map_or("", |index| &text[index + end.len()..])
+}
+
+fn is_xml_whitespace(c: char) -> bool {
+    matches!(c, ' ' | '\t' | '\r' | '\n')
+}
+
+/// The values of the internal general entities declared in `doctype`, which are the entities that
+/// may be referred to in the document
+///
+/// As in XML, the first declaration of an entity is the one that is used.
+pub(crate) fn internal_entities(doctype: &str) -> HashMap<String, String> {
+    let mut entities = HashMap::new();
+    for declaration in entity_declarations(doctype) {
+        if let (false, Some(value)) = (declaration.parameter, declaration.value) {
+            entities
+                .entry(declaration.name.to_string())
+                .or_insert_with(|| value.to_string());
+        }
+    }
+    entities
+}
+
+/// The number of characters that each of `entities` fully expands to, or `None` if expanding any
+/// of them would produce more than `max_length` characters, or refer to entities more than
+/// `max_depth` deep
+///
+/// An entity that refers to itself, directly or not, can never be fully expanded, so always
+/// exceeds the budget.
+pub(crate) fn expansion_lengths(
+    entities: &HashMap<String, String>,
+    max_length: usize,
+    max_depth: u8,
+) -> Option<HashMap<String, usize>> {
+    let mut expanded = HashMap::new();
+    let mut lengths = HashMap::new();
+    for name in entities.keys() {
+        let (length, depth) = expansion(entities, name, &mut expanded, &mut Vec::new())?;
+        if length > max_length || depth > max_depth {
+            return None;
+        }
+        lengths.insert(name.clone(), length);
+    }
+    Some(lengths)
+}
+
+/// The length in characters and the depth of the full expansion of the entity `name`, or `None`
+/// if it refers to itself
+///
+/// Expansions are remembered in `expanded`, so that entities that are referred to many times are
+/// only expanded once, and `expanding` holds the entities whose expansion is in progress.
+fn expansion<'e>(
+    entities: &'e HashMap<String, String>,
+    name: &'e str,
+    expanded: &mut HashMap<&'e str, (usize, u8)>,
+    expanding: &mut Vec<&'e str>,
+) -> Option<(usize, u8)> {
+    if let Some(expansion) = expanded.get(name) {
+        return Some(*expansion);
+    }
+    if expanding.contains(&name) {
+        return None;
+    }
+    let value = entities.get(name)?;
+
+    expanding.push(name);
+    let mut length = 0_usize;
+    let mut depth = 0_u8;
+    let mut rest = value.as_str();
+    while let Some(index) = rest.find('&') {
+        length = length.saturating_add(rest[..index].chars().count());
+        let Some((reference, after)) = rest[index + 1..].split_once(';') else {
+            break;
+        };
+        // Character references and entities that are not declared here expand to a little text
+        // at most, which the parser checks for itself
+        let (reference_length, reference_depth) = if entities.contains_key(reference) {
+            expansion(entities, reference, expanded, expanding)?
+        } else {
+            (1, 0)
+        };
+        length = length.saturating_add(reference_length);
+        depth = depth.max(reference_depth);
+        rest = after;
+    }
+    length = length.saturating_add(rest.chars().count());
+    expanding.pop();
+
+    let expansion = (length, depth.saturating_add(1));
+    expanded.insert(name, expansion);
+    Some(expansion)
+}
@@ -1,46 +1,466 @@
 use thiserror::Error;
 
+use crate::events::XmlEvent;
+
 #[derive(Debug, Error)]
 #[non_exhaustive]
 pub enum XmlWriteError {
     #[error("Failed to serialize XML while writing {element}: {error}")]
     XmlElementWriteError {
         #[source]
-        error: xml::writer::Error,
+        error: Box<dyn std::error::Error + Send + Sync>,
+        element: String,
+    },
+    #[error("Serialized XML was not valid UTF-8: {error}")]
+    InvalidUtf8Output {
+        #[source]
+        error: std::string::FromUtf8Error,
+    },
+    #[error("Failed to write XML for {element}: {error}")]
+    IoError {
+        #[source]
+        error: std::io::Error,
+        element: String,
+    },
+    #[error("Cannot write {name:?} as an XML name in {element}")]
+    InvalidName { name: String, element: String },
+    #[error("Cannot write {value:?} in {element}, as it contains characters that are not allowed in XML")]
+    InvalidCharacters { value: String, element: String },
+    #[error("Cannot write {value:?} in {element}, as documents are written as UTF-8 and only ASCII is written the same in the declared encoding {encoding}")]
+    UnencodableCharacters {
+        value: String,
+        encoding: String,
+        element: String,
+    },
+    #[error("Cannot declare the encoding {encoding}, as documents are written as UTF-8 and it does not write ASCII the same way")]
+    UnsupportedEncoding { encoding: String },
+    #[error("Invalid {element}: {error}")]
+    Custom {
+        #[source]
+        error: Box<dyn std::error::Error + Send + Sync>,
         element: String,
     },
+    #[error("Finished writing the document with the elements {} still open", .elements.join(", "))]
+    UnclosedElements { elements: Vec<String> },
+    #[error("Cannot write outside of an element")]
+    NoOpenElement,
+}
+
+impl XmlWriteError {
+    /// Report an error from a hand-written [`ToXmlElement`](crate::ToXmlElement) implementation
+    pub fn custom(
+        element: impl ToString,
+        error: impl Into<Box<dyn std::error::Error + Send + Sync>>,
+    ) -> Self {
+        XmlWriteError::Custom {
+            error: error.into(),
+            element: element.to_string(),
+        }
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
 #[non_exhaustive]
 pub enum XmlReadError {
-    #[error("Failed to deserialize XML while reading {element}: {error}")]
+    #[error("Failed to deserialize XML while reading {element}{}: {error}", display_location(.position, .path))]
     ElementReadError {
         #[source]
-        error: xml::reader::Error,
+        error: Box<dyn std::error::Error + Send + Sync>,
+        element: String,
+        position: Option<TextPosition>,
+        path: Option<ElementPath>,
+    },
+    #[error("Failed to read XML while reading {element}{}: {error}", display_location(.position, .path))]
+    IoError {
+        #[source]
+        error: std::io::Error,
         element: String,
+        position: Option<TextPosition>,
+        path: Option<ElementPath>,
+    },
+    #[error("Exceeded the limit of {limit} while reading {element}{}", display_location(.position, .path))]
+    LimitExceeded {
+        limit: ReadLimit,
+        element: String,
+        position: Option<TextPosition>,
+        path: Option<ElementPath>,
+    },
+    #[error("Document {violation}, which is not allowed by the read options{}", display_location(.position, .path))]
+    DoctypeNotAllowed {
+        violation: DoctypeViolation,
+        position: Option<TextPosition>,
+        path: Option<ElementPath>,
+    },
+    #[error("Found {} while reading {element}{}{}", display_found(.found, .found_name), display_expected(.expected), display_location(.position, .path))]
+    UnexpectedElementReadError {
+        /// The kind of event that was found
+        found: XmlEventKind,
+        /// The name of the element or processing instruction that was found, if it has one
+        found_name: Option<Box<str>>,
+        /// The names of the elements that could have been read instead, if they are known
+        expected: Box<[String]>,
+        element: String,
+        position: Option<TextPosition>,
+        path: Option<ElementPath>,
     },
-    #[error("Got unexpected XML element when reading {element}: {error}")]
-    UnexpectedElementReadError { error: String, element: String },
 
-    #[error("Ended element {element} without data for required field {required_field}")]
+    #[error("Ended element {element}{} without data for required field {required_field}", display_location(.position, .path))]
     RequiredDataMissing {
         required_field: String,
         element: String,
+        position: Option<TextPosition>,
+        path: Option<ElementPath>,
     },
 
-    #[error("Could not parse {value} as {data_type} on {element}")]
+    #[error("Could not parse {value} as {data_type} on {element}{}", display_location(.position, .path))]
     InvalidParseError {
         value: String,
         data_type: String,
         element: String,
+        position: Option<TextPosition>,
+        path: Option<ElementPath>,
     },
 
     #[error(
-        "Expected document to be in the form {expected_namespace}, but received {}", .actual_namespace.as_ref().unwrap_or(&"no namespace".to_string())
+        "Expected document to be in the form {expected_namespace}, but received {}{}", .actual_namespace.as_ref().unwrap_or(&"no namespace".to_string()), display_location(.position, .path)
     )]
     InvalidNamespaceError {
         expected_namespace: String,
         actual_namespace: Option<String>,
+        position: Option<TextPosition>,
+        path: Option<ElementPath>,
+    },
+
+    #[error("Invalid {element}{}: {error}", display_location(.position, .path))]
+    Custom {
+        #[source]
+        error: Box<dyn std::error::Error + Send + Sync>,
+        element: String,
+        position: Option<TextPosition>,
+        path: Option<ElementPath>,
+    },
+
+    #[error("None of the variants of {element} could be read{}: {}", display_location(.position, .path), display_variant_errors(.errors))]
+    NoMatchingVariant {
+        /// The name of each variant that was tried, in order, with the error it failed with
+        errors: Vec<(String, XmlReadError)>,
+        element: String,
+        position: Option<TextPosition>,
+        path: Option<ElementPath>,
+    },
+
+    #[error("Found xsi:type {xsi_type:?} on {element}{}{}", display_expected_types(.expected), display_location(.position, .path))]
+    UnknownXsiType {
+        /// The type as it was written in the `xsi:type` attribute, e.g. `ext:DerivedType`
+        xsi_type: String,
+        /// The types that could have been read, in the form `{namespace}name`
+        expected: Box<[String]>,
+        element: String,
+        position: Option<TextPosition>,
+        path: Option<ElementPath>,
+    },
+}
+
+impl XmlReadError {
+    /// Report an error from a hand-written [`FromXmlElement`](crate::FromXmlElement)
+    /// implementation, e.g. a value that does not match a pattern
+    ///
+    /// The position and path of the error are filled in from the reader when it is returned.
+    pub fn custom(
+        element: impl ToString,
+        error: impl Into<Box<dyn std::error::Error + Send + Sync>>,
+    ) -> Self {
+        XmlReadError::Custom {
+            error: error.into(),
+            element: element.to_string(),
+            position: None,
+            path: None,
+        }
+    }
+
+    /// The position in the document of the event that caused the error, if it is known
+    pub fn position(&self) -> Option<TextPosition> {
+        match self {
+            XmlReadError::ElementReadError { position, .. }
+            | XmlReadError::IoError { position, .. }
+            | XmlReadError::LimitExceeded { position, .. }
+            | XmlReadError::DoctypeNotAllowed { position, .. }
+            | XmlReadError::UnexpectedElementReadError { position, .. }
+            | XmlReadError::RequiredDataMissing { position, .. }
+            | XmlReadError::InvalidParseError { position, .. }
+            | XmlReadError::InvalidNamespaceError { position, .. }
+            | XmlReadError::Custom { position, .. }
+            | XmlReadError::NoMatchingVariant { position, .. }
+            | XmlReadError::UnknownXsiType { position, .. } => *position,
+        }
+    }
+
+    /// The path of the element that was being read when the error occurred, if it is known
+    pub fn path(&self) -> Option<&ElementPath> {
+        match self {
+            XmlReadError::ElementReadError { path, .. }
+            | XmlReadError::IoError { path, .. }
+            | XmlReadError::LimitExceeded { path, .. }
+            | XmlReadError::DoctypeNotAllowed { path, .. }
+            | XmlReadError::UnexpectedElementReadError { path, .. }
+            | XmlReadError::RequiredDataMissing { path, .. }
+            | XmlReadError::InvalidParseError { path, .. }
+            | XmlReadError::InvalidNamespaceError { path, .. }
+            | XmlReadError::Custom { path, .. }
+            | XmlReadError::NoMatchingVariant { path, .. }
+            | XmlReadError::UnknownXsiType { path, .. } => path.as_ref(),
+        }
+    }
+
+    /// Whether reading can carry on after this error by skipping the element it occurred in
+    ///
+    /// Errors in the XML syntax itself are not recoverable, since the rest of the document cannot
+    /// be trusted, but errors about the content of well-formed elements are.
+    pub fn is_recoverable(&self) -> bool {
+        match self {
+            XmlReadError::ElementReadError { .. }
+            | XmlReadError::IoError { .. }
+            | XmlReadError::LimitExceeded { .. }
+            | XmlReadError::DoctypeNotAllowed { .. } => false,
+            XmlReadError::UnexpectedElementReadError { .. }
+            | XmlReadError::RequiredDataMissing { .. }
+            | XmlReadError::InvalidParseError { .. }
+            | XmlReadError::InvalidNamespaceError { .. }
+            | XmlReadError::Custom { .. }
+            | XmlReadError::NoMatchingVariant { .. }
+            | XmlReadError::UnknownXsiType { .. } => true,
+        }
+    }
+
+    /// Record `new_position` as the position of the error, unless it already has one
+    ///
+    /// This is useful for errors created without access to the reader, e.g. by
+    /// [`parse_xml_text`](crate::xml_utils::parse_xml_text).
+    pub fn with_position(mut self, new_position: TextPosition) -> Self {
+        match &mut self {
+            XmlReadError::ElementReadError { position, .. }
+            | XmlReadError::IoError { position, .. }
+            | XmlReadError::LimitExceeded { position, .. }
+            | XmlReadError::DoctypeNotAllowed { position, .. }
+            | XmlReadError::UnexpectedElementReadError { position, .. }
+            | XmlReadError::RequiredDataMissing { position, .. }
+            | XmlReadError::InvalidParseError { position, .. }
+            | XmlReadError::InvalidNamespaceError { position, .. }
+            | XmlReadError::Custom { position, .. }
+            | XmlReadError::NoMatchingVariant { position, .. }
+            | XmlReadError::UnknownXsiType { position, .. } => {
+                position.get_or_insert(new_position);
+            }
+        }
+        self
+    }
+
+    /// Record `new_path` as the path of the error, unless it already has one
+    pub fn with_path(mut self, new_path: &ElementPath) -> Self {
+        match &mut self {
+            XmlReadError::ElementReadError { path, .. }
+            | XmlReadError::IoError { path, .. }
+            | XmlReadError::LimitExceeded { path, .. }
+            | XmlReadError::DoctypeNotAllowed { path, .. }
+            | XmlReadError::UnexpectedElementReadError { path, .. }
+            | XmlReadError::RequiredDataMissing { path, .. }
+            | XmlReadError::InvalidParseError { path, .. }
+            | XmlReadError::InvalidNamespaceError { path, .. }
+            | XmlReadError::Custom { path, .. }
+            | XmlReadError::NoMatchingVariant { path, .. }
+            | XmlReadError::UnknownXsiType { path, .. } => {
+                path.get_or_insert_with(|| new_path.clone());
+            }
+        }
+        self
+    }
+}
+
+/// A position in a document, where both the line and the column start at 1
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TextPosition {
+    pub line: u64,
+    pub column: u64,
+}
+
+impl std::fmt::Display for TextPosition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// The path to an element from the root of the document, e.g. `/bom/components/component[17]`
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ElementPath {
+    pub segments: Vec<ElementPathSegment>,
+}
+
+/// An element in an [`ElementPath`]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ElementPathSegment {
+    pub name: String,
+    /// The position of the element among its siblings with the same name, starting at 1
+    pub index: usize,
+}
+
+impl std::fmt::Display for ElementPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.segments.is_empty() {
+            return write!(f, "/");
+        }
+
+        for segment in &self.segments {
+            write!(f, "/{}", segment)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for ElementPathSegment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.index > 1 {
+            write!(f, "{}[{}]", self.name, self.index)
+        } else {
+            write!(f, "{}", self.name)
+        }
+    }
+}
+
+/// A limit from the [`ReadOptions`](crate::ReadOptions) that a document exceeded, along with its
+/// configured value
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ReadLimit {
+    Depth(usize),
+    Elements(usize),
+    TextLength(usize),
+    Attributes(usize),
+    /// The budget for expanding entities given by
+    /// [`DoctypePolicy::AllowInternalEntities`](crate::DoctypePolicy::AllowInternalEntities)
+    EntityExpansion {
+        max_expansion_length: usize,
+        max_expansion_depth: u8,
     },
 }
+
+impl std::fmt::Display for ReadLimit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReadLimit::Depth(max) => write!(f, "{} nested elements", max),
+            ReadLimit::Elements(max) => write!(f, "{} elements", max),
+            ReadLimit::TextLength(max) => write!(f, "{} bytes of text", max),
+            ReadLimit::Attributes(max) => write!(f, "{} attributes on an element", max),
+            ReadLimit::EntityExpansion {
+                max_expansion_length,
+                max_expansion_depth,
+            } => write!(
+                f,
+                "{} characters or {} levels of entity expansion",
+                max_expansion_length, max_expansion_depth
+            ),
+        }
+    }
+}
+
+/// The part of a `<!DOCTYPE>` that the [`DoctypePolicy`](crate::DoctypePolicy) in the
+/// read options does not allow
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum DoctypeViolation {
+    Doctype,
+    EntityDeclaration,
+    ExternalEntityDeclaration,
+}
+
+impl std::fmt::Display for DoctypeViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DoctypeViolation::Doctype => write!(f, "has a DOCTYPE"),
+            DoctypeViolation::EntityDeclaration => write!(f, "declares entities"),
+            DoctypeViolation::ExternalEntityDeclaration => {
+                write!(f, "declares an external entity")
+            }
+        }
+    }
+}
+
+/// The kinds of event that can be found in a document
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum XmlEventKind {
+    StartDocument,
+    EndDocument,
+    StartElement,
+    EndElement,
+    /// Characters, CDATA or whitespace
+    Text,
+    Comment,
+    ProcessingInstruction,
+}
+
+impl<S> From<&XmlEvent<S>> for XmlEventKind {
+    fn from(event: &XmlEvent<S>) -> Self {
+        match event {
+            XmlEvent::StartDocument { .. } => XmlEventKind::StartDocument,
+            XmlEvent::EndDocument => XmlEventKind::EndDocument,
+            XmlEvent::StartElement { .. } => XmlEventKind::StartElement,
+            XmlEvent::EndElement { .. } => XmlEventKind::EndElement,
+            XmlEvent::Characters(_) | XmlEvent::CData(_) | XmlEvent::Whitespace(_) => {
+                XmlEventKind::Text
+            }
+            XmlEvent::Comment(_) => XmlEventKind::Comment,
+            XmlEvent::ProcessingInstruction { .. } => XmlEventKind::ProcessingInstruction,
+        }
+    }
+}
+
+fn display_found(found: &XmlEventKind, found_name: &Option<Box<str>>) -> String {
+    let name = found_name.as_deref().unwrap_or_default();
+    match found {
+        XmlEventKind::StartDocument => "the XML declaration".to_string(),
+        XmlEventKind::EndDocument => "the end of the document".to_string(),
+        XmlEventKind::StartElement => format!("`<{}>`", name),
+        XmlEventKind::EndElement => format!("`</{}>`", name),
+        XmlEventKind::Text => "text".to_string(),
+        XmlEventKind::Comment => "a comment".to_string(),
+        XmlEventKind::ProcessingInstruction => format!("`<?{}?>`", name),
+    }
+}
+
+fn display_expected(expected: &[String]) -> String {
+    let expected_elements = expected
+        .iter()
+        .map(|name| format!("`<{}>`", name))
+        .collect::<Vec<_>>()
+        .join(", ");
+    match expected.len() {
+        0 => String::new(),
+        1 => format!(", expected {}", expected_elements),
+        _ => format!(", expected one of {}", expected_elements),
+    }
+}
+
+fn display_expected_types(expected: &[String]) -> String {
+    match expected.len() {
+        0 => String::new(),
+        1 => format!(", expected {}", expected[0]),
+        _ => format!(", expected one of {}", expected.join(", ")),
+    }
+}
+
+fn display_variant_errors(errors: &[(String, XmlReadError)]) -> String {
+    errors
+        .iter()
+        .map(|(variant, error)| format!("{} failed with \"{}\"", variant, error))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn display_location(position: &Option<TextPosition>, path: &Option<ElementPath>) -> String {
+    match (position, path) {
+        (Some(position), Some(path)) => format!(" at {} ({})", path, position),
+        (Some(position), None) => format!(" at {}", position),
+        (None, Some(path)) => format!(" at {}", path),
+        (None, None) => String::new(),
+    }
+}
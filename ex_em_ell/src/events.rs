@@ -0,0 +1,267 @@
+use std::{borrow::Cow, collections::BTreeMap};
+
+use crate::options::XmlVersion;
+
+/// The name of an element or attribute, along with the namespace it was resolved to
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct XmlName {
+    pub local_name: String,
+    pub namespace: Option<String>,
+    pub prefix: Option<String>,
+}
+
+impl XmlName {
+    /// A name without a namespace or prefix
+    pub fn local(local_name: impl Into<String>) -> Self {
+        Self {
+            local_name: local_name.into(),
+            namespace: None,
+            prefix: None,
+        }
+    }
+
+    /// The name as it was written in the document, e.g. `ext:field`
+    pub fn prefixed(&self) -> String {
+        match &self.prefix {
+            Some(prefix) => format!("{}:{}", prefix, self.local_name),
+            None => self.local_name.clone(),
+        }
+    }
+}
+
+/// Displays the name in the form `{namespace}prefix:local_name`, omitting the parts it doesn't
+/// have
+impl std::fmt::Display for XmlName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(namespace) = &self.namespace {
+            write!(f, "{{{}}}", namespace)?;
+        }
+        if let Some(prefix) = &self.prefix {
+            write!(f, "{}:", prefix)?;
+        }
+        f.write_str(&self.local_name)
+    }
+}
+
+/// An attribute of an element
+///
+/// The value is a [`String`], unless it was read with
+/// [`next_borrowed_event`](crate::XmlEventReader::next_borrowed_event).
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct XmlAttribute<S = String> {
+    pub name: XmlName,
+    pub value: S,
+}
+
+/// The namespaces that are in scope at an element, as a map from prefixes to namespace URIs
+///
+/// The default namespace, if there is one, has the empty prefix.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Namespace(pub BTreeMap<String, String>);
+
+impl Namespace {
+    /// The namespace URI that `prefix` refers to
+    pub fn get(&self, prefix: &str) -> Option<&str> {
+        self.0.get(prefix).map(String::as_str)
+    }
+
+    /// The prefixes and namespace URIs that are in scope
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.0
+            .iter()
+            .map(|(prefix, uri)| (prefix.as_str(), uri.as_str()))
+    }
+}
+
+/// An event read from a document by an [`XmlEventSource`](crate::backend::XmlEventSource)
+///
+/// The text of the event is a [`String`], unless it was read with
+/// [`next_borrowed_event`](crate::XmlEventReader::next_borrowed_event), which borrows it from the
+/// document as a [`Cow`] where it can.
+#[derive(Clone, Debug, PartialEq)]
+pub enum XmlEvent<S = String> {
+    StartDocument {
+        version: XmlVersion,
+        encoding: String,
+        standalone: Option<bool>,
+    },
+    EndDocument,
+    ProcessingInstruction {
+        name: String,
+        data: Option<String>,
+    },
+    StartElement {
+        name: XmlName,
+        attributes: Vec<XmlAttribute<S>>,
+        namespace: Namespace,
+    },
+    EndElement {
+        name: XmlName,
+    },
+    CData(S),
+    Comment(S),
+    Characters(S),
+    Whitespace(S),
+}
+
+impl<S> XmlEvent<S> {
+    /// Convert the text of the event, leaving the rest of it as it is
+    fn map_text<T>(self, mut f: impl FnMut(S) -> T) -> XmlEvent<T> {
+        match self {
+            XmlEvent::StartDocument {
+                version,
+                encoding,
+                standalone,
+            } => XmlEvent::StartDocument {
+                version,
+                encoding,
+                standalone,
+            },
+            XmlEvent::EndDocument => XmlEvent::EndDocument,
+            XmlEvent::ProcessingInstruction { name, data } => {
+                XmlEvent::ProcessingInstruction { name, data }
+            }
+            XmlEvent::StartElement {
+                name,
+                attributes,
+                namespace,
+            } => XmlEvent::StartElement {
+                name,
+                attributes: attributes
+                    .into_iter()
+                    .map(|attribute| XmlAttribute {
+                        name: attribute.name,
+                        value: f(attribute.value),
+                    })
+                    .collect(),
+                namespace,
+            },
+            XmlEvent::EndElement { name } => XmlEvent::EndElement { name },
+            XmlEvent::CData(text) => XmlEvent::CData(f(text)),
+            XmlEvent::Comment(comment) => XmlEvent::Comment(f(comment)),
+            XmlEvent::Characters(text) => XmlEvent::Characters(f(text)),
+            XmlEvent::Whitespace(text) => XmlEvent::Whitespace(f(text)),
+        }
+    }
+}
+
+impl XmlEvent<Cow<'_, str>> {
+    /// Copy any text that is borrowed from the document
+    pub fn into_owned(self) -> XmlEvent {
+        self.map_text(Cow::into_owned)
+    }
+}
+
+impl From<XmlEvent> for XmlEvent<Cow<'_, str>> {
+    fn from(event: XmlEvent) -> Self {
+        event.map_text(Cow::Owned)
+    }
+}
+
+/// An event written to a document by an [`XmlEventSink`](crate::backend::XmlEventSink)
+#[derive(Clone, Debug, PartialEq)]
+pub enum WriteEvent<'a> {
+    StartDocument {
+        version: XmlVersion,
+        encoding: &'a str,
+        standalone: Option<bool>,
+    },
+    ProcessingInstruction {
+        name: &'a str,
+        data: Option<&'a str>,
+    },
+    StartElement {
+        /// The name of the element, which may have a prefix, e.g. `ext:field`
+        name: &'a str,
+        /// The names and values of the attributes, whose names may have a prefix
+        attributes: Vec<(&'a str, &'a str)>,
+        /// The namespaces declared on the element, as prefixes and namespace URIs, where the
+        /// default namespace has the empty prefix
+        namespaces: Vec<(&'a str, &'a str)>,
+    },
+    /// The end of the element that was started last
+    EndElement,
+    CData(&'a str),
+    Comment(&'a str),
+    Characters(&'a str),
+}
+
+impl<'a> WriteEvent<'a> {
+    /// Start the element `name`, adding any attributes and namespaces with the builder
+    pub fn start_element(name: &'a str) -> StartElementBuilder<'a> {
+        StartElementBuilder {
+            name,
+            attributes: Vec::new(),
+            namespaces: Vec::new(),
+        }
+    }
+
+    pub fn end_element() -> Self {
+        WriteEvent::EndElement
+    }
+
+    pub fn characters(text: &'a str) -> Self {
+        WriteEvent::Characters(text)
+    }
+
+    pub fn cdata(text: &'a str) -> Self {
+        WriteEvent::CData(text)
+    }
+
+    pub fn comment(comment: &'a str) -> Self {
+        WriteEvent::Comment(comment)
+    }
+
+    pub fn processing_instruction(name: &'a str, data: Option<&'a str>) -> Self {
+        WriteEvent::ProcessingInstruction { name, data }
+    }
+}
+
+/// A builder for [`WriteEvent::StartElement`], created by [`WriteEvent::start_element`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct StartElementBuilder<'a> {
+    name: &'a str,
+    attributes: Vec<(&'a str, &'a str)>,
+    namespaces: Vec<(&'a str, &'a str)>,
+}
+
+impl<'a> StartElementBuilder<'a> {
+    /// The name of the element that is being started
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    pub fn attr(mut self, name: &'a str, value: &'a str) -> Self {
+        self.attributes.push((name, value));
+        self
+    }
+
+    /// Add the attribute if it has a value
+    pub fn optional_attr(self, name: &'a str, value: Option<&'a str>) -> Self {
+        match value {
+            Some(value) => self.attr(name, value),
+            None => self,
+        }
+    }
+
+    /// Declare the namespace `uri` with `prefix`
+    pub fn ns(mut self, prefix: &'a str, uri: &'a str) -> Self {
+        self.namespaces.push((prefix, uri));
+        self
+    }
+
+    /// Declare `uri` as the default namespace
+    pub fn default_ns(self, uri: &'a str) -> Self {
+        self.ns("", uri)
+    }
+}
+
+impl<'a> From<StartElementBuilder<'a>> for WriteEvent<'a> {
+    fn from(builder: StartElementBuilder<'a>) -> Self {
+        WriteEvent::StartElement {
+            name: builder.name,
+            attributes: builder.attributes,
+            namespaces: builder.namespaces,
+        }
+    }
+}
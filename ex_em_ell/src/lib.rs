@@ -1,46 +1,201 @@
 #![doc = include_str!("../README.md")]
 
+#[cfg(feature = "tokio")]
+pub mod async_io;
+pub mod backend;
+mod doctype;
 pub mod errors;
+pub mod events;
+pub mod nillable;
+pub mod options;
+pub mod reader;
+pub mod stream;
 pub mod traits;
+pub mod writer;
 pub mod xml_utils;
 
-use std::io::Read;
+use std::io::{Read, Write};
 
+use backend::{
+    xml_rs::{XmlRsSink, XmlRsSource},
+    XmlBorrowedEventSource, XmlEventSink, XmlEventSource,
+};
 use errors::{XmlReadError, XmlWriteError};
-use xml::{EmitterConfig, EventReader, EventWriter, ParserConfig};
 
 #[cfg(feature = "derive")]
 pub use ex_em_ell_derive::{
-    FromXmlDocument, FromXmlElement, NamedXmlElement, ToXmlDocument, ToXmlElement,
+    FromXmlDocument, FromXmlDocumentBorrowed, FromXmlElement, FromXmlElementBorrowed,
+    NamedXmlElement, ToXmlDocument, ToXmlElement,
 };
 
-pub use traits::{FromXmlDocument, FromXmlElement, NamedXmlElement, ToXmlDocument, ToXmlElement};
-pub extern crate xml;
+pub use nillable::Nillable;
+pub use options::{DoctypePolicy, ReadOptions, WriteOptions, XmlVersion};
+pub use reader::XmlEventReader;
+pub use traits::{
+    FromXmlDocument, FromXmlDocumentBorrowed, FromXmlElement, FromXmlElementBorrowed,
+    FromXmlTextBorrowed, NamedXmlElement, ToXmlDocument, ToXmlElement, XmlSimpleType,
+};
+pub use writer::XmlDocumentWriter;
 
 pub fn to_string<T: ToXmlDocument>(value: &T) -> Result<String, XmlWriteError> {
-    to_string_with_config(value, EmitterConfig::default())
+    to_string_with_options(value, WriteOptions::default())
 }
 
 pub fn to_string_pretty<T: ToXmlDocument>(value: &T) -> Result<String, XmlWriteError> {
-    let config = EmitterConfig::default().perform_indent(true);
-    to_string_with_config(value, config)
+    to_string_with_options(value, WriteOptions::default().perform_indent(true))
 }
 
-fn to_string_with_config<T: ToXmlDocument>(
+pub fn to_string_with_options<T: ToXmlDocument>(
     value: &T,
-    config: EmitterConfig,
+    options: WriteOptions,
 ) -> Result<String, XmlWriteError> {
     let mut output = Vec::new();
-    let mut event_writer = EventWriter::new_with_config(&mut output, config);
+    to_writer_with_options(&mut output, value, options)?;
+
+    String::from_utf8(output).map_err(|error| XmlWriteError::InvalidUtf8Output { error })
+}
+
+/// Stream the document to `writer` as it is serialized, without buffering it in memory
+pub fn to_writer<W: Write, T: ToXmlDocument>(writer: W, value: &T) -> Result<(), XmlWriteError> {
+    to_writer_with_options(writer, value, WriteOptions::default())
+}
+
+/// Stream the indented document to `writer` as it is serialized, without buffering it in memory
+pub fn to_writer_pretty<W: Write, T: ToXmlDocument>(
+    writer: W,
+    value: &T,
+) -> Result<(), XmlWriteError> {
+    to_writer_with_options(writer, value, WriteOptions::default().perform_indent(true))
+}
 
-    value.to_xml_document(&mut event_writer)?;
+pub fn to_writer_with_options<W: Write, T: ToXmlDocument>(
+    writer: W,
+    value: &T,
+    options: WriteOptions,
+) -> Result<(), XmlWriteError> {
+    to_sink(&mut XmlRsSink::new(writer, &options), value, &options)
+}
+
+/// Write the document to `sink`, which should have been configured with the same `options`
+pub fn to_sink<S: XmlEventSink, T: ToXmlDocument>(
+    sink: &mut S,
+    value: &T,
+    options: &WriteOptions,
+) -> Result<(), XmlWriteError> {
+    writer::write_declaration(sink, options)?;
+
+    value.to_xml_document(sink)
+}
+
+pub fn from_str<T: FromXmlDocument>(value: &str) -> Result<T, XmlReadError> {
+    from_slice(value.as_bytes())
+}
 
-    let output = String::from_utf8_lossy(&output).to_string();
-    Ok(output)
+pub fn from_slice<T: FromXmlDocument>(value: &[u8]) -> Result<T, XmlReadError> {
+    from_reader(value)
 }
 
 pub fn from_reader<T: FromXmlDocument, R: Read>(reader: R) -> Result<T, XmlReadError> {
-    let config = ParserConfig::new().trim_whitespace(true);
-    let mut event_reader = EventReader::new_with_config(reader, config);
-    T::from_xml_document(&mut event_reader)
+    from_reader_with_options(reader, ReadOptions::default())
+}
+
+pub fn from_reader_with_options<T: FromXmlDocument, R: Read>(
+    reader: R,
+    options: ReadOptions,
+) -> Result<T, XmlReadError> {
+    from_source(XmlRsSource::new(reader, &options), options)
+}
+
+/// Read a document from `source`, which should have been configured with the same `options`
+pub fn from_source<T: FromXmlDocument, S: XmlEventSource>(
+    source: S,
+    options: ReadOptions,
+) -> Result<T, XmlReadError> {
+    let mut event_reader = XmlEventReader::from_source(source, options);
+    T::from_xml_document(&mut event_reader).map_err(|error| {
+        error
+            .with_position(event_reader.position())
+            .with_path(event_reader.path())
+    })
+}
+
+/// Read a document that borrows its text from `value`, copying only text that had to be decoded
+#[cfg(feature = "quick-xml")]
+pub fn from_str_borrowed<'de, T: FromXmlDocumentBorrowed<'de>>(
+    value: &'de str,
+) -> Result<T, XmlReadError> {
+    from_str_borrowed_with_options(value, ReadOptions::default())
+}
+
+#[cfg(feature = "quick-xml")]
+pub fn from_str_borrowed_with_options<'de, T: FromXmlDocumentBorrowed<'de>>(
+    value: &'de str,
+    options: ReadOptions,
+) -> Result<T, XmlReadError> {
+    let source = backend::quick_xml::QuickXmlStrSource::new(value, &options);
+    from_source_borrowed(source, options)
+}
+
+/// Read a document that borrows its text from `value`, which must be UTF-8
+#[cfg(feature = "quick-xml")]
+pub fn from_slice_borrowed<'de, T: FromXmlDocumentBorrowed<'de>>(
+    value: &'de [u8],
+) -> Result<T, XmlReadError> {
+    let value = std::str::from_utf8(value).map_err(|error| XmlReadError::ElementReadError {
+        error: Box::new(error),
+        element: "document".to_string(),
+        position: None,
+        path: None,
+    })?;
+    from_str_borrowed(value)
+}
+
+/// Read a document that borrows text from `source`, which should have been configured with the
+/// same `options`
+pub fn from_source_borrowed<
+    'de,
+    T: FromXmlDocumentBorrowed<'de>,
+    S: XmlBorrowedEventSource<'de>,
+>(
+    source: S,
+    options: ReadOptions,
+) -> Result<T, XmlReadError> {
+    let mut event_reader = XmlEventReader::from_source(source, options);
+    T::from_xml_document_borrowed(&mut event_reader).map_err(|error| {
+        error
+            .with_position(event_reader.position())
+            .with_path(event_reader.path())
+    })
+}
+
+/// Read a document, carrying on past recoverable errors so that all of them can be reported
+///
+/// An element with an error is skipped and reading continues with its next sibling. Errors in the
+/// XML syntax itself still stop reading, and are reported after any errors found before them.
+pub fn from_reader_collect_errors<T: FromXmlDocument, R: Read>(
+    reader: R,
+) -> Result<T, Vec<XmlReadError>> {
+    from_reader_collect_errors_with_options(reader, ReadOptions::default())
+}
+
+pub fn from_reader_collect_errors_with_options<T: FromXmlDocument, R: Read>(
+    reader: R,
+    options: ReadOptions,
+) -> Result<T, Vec<XmlReadError>> {
+    let mut event_reader = XmlEventReader::new_with_options(reader, options);
+    event_reader.collect_errors();
+    let result = T::from_xml_document(&mut event_reader);
+    let mut errors = event_reader.take_collected_errors();
+    match result {
+        Ok(value) if errors.is_empty() => Ok(value),
+        Ok(_) => Err(errors),
+        Err(error) => {
+            errors.push(
+                error
+                    .with_position(event_reader.position())
+                    .with_path(event_reader.path()),
+            );
+            Err(errors)
+        }
+    }
 }
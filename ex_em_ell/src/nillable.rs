@@ -0,0 +1,139 @@
+//! Elements declared with `nillable="true"` in an XML Schema, which can be written as
+//! `<element xsi:nil="true"/>` to give them no value
+
+use std::borrow::Cow;
+
+use crate::{
+    backend::{XmlBorrowedEventSource, XmlEventSink, XmlEventSource},
+    errors::{XmlReadError, XmlWriteError},
+    events::{Namespace, XmlAttribute, XmlName},
+    reader::XmlEventReader,
+    xml_utils::{read_nil_element, read_xsi_nil, write_nil_element},
+    FromXmlElement, FromXmlElementBorrowed, ToXmlElement,
+};
+
+/// The value of a nillable element, which tells apart an element that is missing from one that
+/// is `xsi:nil`
+///
+/// A missing element is read as [`Nillable::Absent`] rather than being reported as missing, and
+/// is not written.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Nillable<T> {
+    /// The element is missing
+    #[default]
+    Absent,
+    /// The element is present with `xsi:nil="true"`
+    Nil,
+    /// The element is present with a value
+    Present(T),
+}
+
+impl<T> Nillable<T> {
+    pub fn is_absent(&self) -> bool {
+        matches!(self, Nillable::Absent)
+    }
+
+    pub fn is_nil(&self) -> bool {
+        matches!(self, Nillable::Nil)
+    }
+
+    /// The value of the element, if it has one
+    pub fn as_option(&self) -> Option<&T> {
+        match self {
+            Nillable::Present(value) => Some(value),
+            Nillable::Absent | Nillable::Nil => None,
+        }
+    }
+
+    /// The value of the element, if it has one
+    pub fn into_option(self) -> Option<T> {
+        match self {
+            Nillable::Present(value) => Some(value),
+            Nillable::Absent | Nillable::Nil => None,
+        }
+    }
+}
+
+impl<T> From<T> for Nillable<T> {
+    fn from(value: T) -> Self {
+        Nillable::Present(value)
+    }
+}
+
+impl<T> ToXmlElement for Nillable<T>
+where
+    T: ToXmlElement,
+{
+    fn to_xml_element<W: XmlEventSink>(
+        &self,
+        writer: &mut W,
+        tag: &str,
+    ) -> Result<(), XmlWriteError> {
+        match self {
+            Nillable::Absent => Ok(()),
+            Nillable::Nil => write_nil_element(writer, tag),
+            Nillable::Present(value) => value.to_xml_element(writer, tag),
+        }
+    }
+
+    fn will_write(&self) -> bool {
+        match self {
+            Nillable::Absent => false,
+            Nillable::Nil => true,
+            Nillable::Present(value) => value.will_write(),
+        }
+    }
+}
+
+impl<T> FromXmlElement for Nillable<T>
+where
+    T: FromXmlElement,
+{
+    fn from_xml_element<R: XmlEventSource>(
+        reader: &mut XmlEventReader<R>,
+        element_name: &XmlName,
+        element_attributes: &[XmlAttribute],
+        element_namespace: &Namespace,
+    ) -> Result<Self, XmlReadError> {
+        if read_xsi_nil(reader, element_attributes, element_name)? {
+            read_nil_element(reader, element_name)?;
+            Ok(Nillable::Nil)
+        } else {
+            T::from_xml_element(reader, element_name, element_attributes, element_namespace)
+                .map(Nillable::Present)
+        }
+    }
+
+    fn from_missing_element() -> Option<Self> {
+        Some(Nillable::Absent)
+    }
+}
+
+impl<'de, T> FromXmlElementBorrowed<'de> for Nillable<T>
+where
+    T: FromXmlElementBorrowed<'de>,
+{
+    fn from_xml_element_borrowed<R: XmlBorrowedEventSource<'de>>(
+        reader: &mut XmlEventReader<R>,
+        element_name: &XmlName,
+        element_attributes: &[XmlAttribute<Cow<'de, str>>],
+        element_namespace: &Namespace,
+    ) -> Result<Self, XmlReadError> {
+        if read_xsi_nil(reader, element_attributes, element_name)? {
+            read_nil_element(reader, element_name)?;
+            Ok(Nillable::Nil)
+        } else {
+            T::from_xml_element_borrowed(
+                reader,
+                element_name,
+                element_attributes,
+                element_namespace,
+            )
+            .map(Nillable::Present)
+        }
+    }
+
+    fn from_missing_element() -> Option<Self> {
+        Some(Nillable::Absent)
+    }
+}
@@ -0,0 +1,317 @@
+use std::collections::HashMap;
+
+/// Options that control how documents are parsed
+///
+/// The options are carried by the [`XmlEventReader`](crate::reader::XmlEventReader), so they are
+/// available to derived and hand-written readers as well as the underlying parser.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ReadOptions {
+    /// Trim leading and trailing whitespace from text, and skip text that is only whitespace
+    ///
+    /// The text of a simple element is trimmed once its pieces have been joined, so whitespace
+    /// next to a comment or CDATA section inside it is kept.
+    pub trim_whitespace: bool,
+
+    /// Report CDATA sections as regular text
+    pub cdata_to_characters: bool,
+
+    /// Drop comments while parsing, rather than surfacing them to types that collect them
+    pub ignore_comments: bool,
+
+    /// Additional entities, beyond the predefined XML entities, that are expanded in text
+    pub extra_entities: HashMap<String, String>,
+
+    /// Replace references to unknown entities with the Unicode replacement character, rather
+    /// than failing
+    pub replace_unknown_entity_references: bool,
+
+    /// Check that documents are in the namespace declared with `#[ex_em_ell(namespace = "...")]`
+    pub check_namespaces: bool,
+
+    /// The most elements that may be open at once, which bounds the recursion of derived readers
+    pub max_depth: usize,
+
+    /// The most elements that a document may contain in total
+    ///
+    /// This counts every element, not just the repeated ones, and applies to streamed documents
+    /// too. The default of 100,000,000 leaves room for documents with hundreds of thousands of
+    /// components, so lower it when the documents read are known to be small.
+    pub max_elements: usize,
+
+    /// The longest, in bytes, that any piece of text, attribute value, comment, name,
+    /// processing instruction or `<!DOCTYPE>` may be
+    pub max_text_length: usize,
+
+    /// The most attributes, including namespace declarations, that an element may have
+    ///
+    /// This is checked once the whole start tag has been read.
+    pub max_attributes: usize,
+
+    /// Whether a `<!DOCTYPE>` and the entities it declares are accepted
+    pub doctype: DoctypePolicy,
+}
+
+impl ReadOptions {
+    pub fn new() -> Self {
+        Self {
+            trim_whitespace: true,
+            cdata_to_characters: false,
+            ignore_comments: false,
+            extra_entities: HashMap::new(),
+            replace_unknown_entity_references: false,
+            check_namespaces: true,
+            max_depth: 128,
+            max_elements: 100_000_000,
+            max_text_length: 16 * 1024 * 1024,
+            max_attributes: 256,
+            doctype: DoctypePolicy::default(),
+        }
+    }
+
+    pub fn trim_whitespace(mut self, trim_whitespace: bool) -> Self {
+        self.trim_whitespace = trim_whitespace;
+        self
+    }
+
+    pub fn cdata_to_characters(mut self, cdata_to_characters: bool) -> Self {
+        self.cdata_to_characters = cdata_to_characters;
+        self
+    }
+
+    pub fn ignore_comments(mut self, ignore_comments: bool) -> Self {
+        self.ignore_comments = ignore_comments;
+        self
+    }
+
+    pub fn add_entity(mut self, entity: impl Into<String>, value: impl Into<String>) -> Self {
+        self.extra_entities.insert(entity.into(), value.into());
+        self
+    }
+
+    pub fn replace_unknown_entity_references(
+        mut self,
+        replace_unknown_entity_references: bool,
+    ) -> Self {
+        self.replace_unknown_entity_references = replace_unknown_entity_references;
+        self
+    }
+
+    pub fn check_namespaces(mut self, check_namespaces: bool) -> Self {
+        self.check_namespaces = check_namespaces;
+        self
+    }
+
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    pub fn max_elements(mut self, max_elements: usize) -> Self {
+        self.max_elements = max_elements;
+        self
+    }
+
+    pub fn max_text_length(mut self, max_text_length: usize) -> Self {
+        self.max_text_length = max_text_length;
+        self
+    }
+
+    pub fn max_attributes(mut self, max_attributes: usize) -> Self {
+        self.max_attributes = max_attributes;
+        self
+    }
+
+    pub fn doctype(mut self, doctype: DoctypePolicy) -> Self {
+        self.doctype = doctype;
+        self
+    }
+}
+
+/// How a `<!DOCTYPE>` in a document is treated
+///
+/// Entities declared in a DOCTYPE are expanded by the parser, so a small document can declare
+/// entities that expand to an enormous amount of text (the "billion laughs" attack). External
+/// entities are never fetched, but are still rejected by every policy.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum DoctypePolicy {
+    /// Reject any document with a DOCTYPE
+    Reject,
+    /// Accept a DOCTYPE, but reject any document whose DOCTYPE declares entities
+    ///
+    /// This is the default.
+    #[default]
+    AllowWithoutEntities,
+    /// Accept entities declared in the DOCTYPE, expanding them within a budget
+    AllowInternalEntities {
+        /// The most characters that the entities referred to in the document may expand to in
+        /// total, including the entities they refer to
+        max_expansion_length: usize,
+        /// How deeply entities may refer to other entities
+        max_expansion_depth: u8,
+    },
+}
+
+impl DoctypePolicy {
+    /// Accept internal entities, with a budget of 1,000,000 characters and a depth of 10
+    pub fn allow_internal_entities() -> Self {
+        DoctypePolicy::AllowInternalEntities {
+            max_expansion_length: 1_000_000,
+            max_expansion_depth: 10,
+        }
+    }
+
+    pub(crate) fn max_expansion_length(&self) -> usize {
+        match self {
+            DoctypePolicy::AllowInternalEntities {
+                max_expansion_length,
+                ..
+            } => *max_expansion_length,
+            _ => 0,
+        }
+    }
+
+    #[cfg(feature = "quick-xml")]
+    pub(crate) fn max_expansion_depth(&self) -> u8 {
+        match self {
+            DoctypePolicy::AllowInternalEntities {
+                max_expansion_depth,
+                ..
+            } => *max_expansion_depth,
+            _ => 0,
+        }
+    }
+}
+
+impl Default for ReadOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The version in the XML declaration
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum XmlVersion {
+    Version10,
+    Version11,
+}
+
+/// Options that control how documents are written
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct WriteOptions {
+    /// Write the `<?xml ...?>` declaration at the start of the document
+    pub write_document_declaration: bool,
+
+    /// The version in the XML declaration
+    ///
+    /// Text is checked against the characters allowed by this version, so that the document can
+    /// be read back.
+    pub xml_version: XmlVersion,
+
+    /// The encoding named in the XML declaration
+    ///
+    /// The document itself is always written as UTF-8, so only encodings that write ASCII the
+    /// same way can be declared (e.g. `US-ASCII` or `ISO-8859-1`, but not `UTF-16`). Unless it is
+    /// UTF-8, writing a name or text that is not ASCII fails with
+    /// [`XmlWriteError::UnencodableCharacters`](crate::errors::XmlWriteError::UnencodableCharacters).
+    pub encoding: String,
+
+    /// The `standalone` flag of the XML declaration, which is omitted when `None`
+    pub standalone: Option<bool>,
+
+    /// Write each element on its own line, indented by its depth
+    pub perform_indent: bool,
+
+    /// The string used for a single level of indentation
+    pub indent_string: String,
+
+    /// The string written between lines when indenting
+    pub line_separator: String,
+
+    /// Write elements without content as `<a />` rather than `<a></a>`
+    ///
+    /// Simple elements always have text content, even when it is empty, so an empty string is
+    /// still written as `<a></a>`.
+    pub self_closing_empty_elements: bool,
+
+    /// Write a space before the end of self-closing elements, i.e. `<a />` rather than `<a/>`
+    pub pad_self_closing: bool,
+}
+
+impl WriteOptions {
+    pub fn new() -> Self {
+        Self {
+            write_document_declaration: true,
+            xml_version: XmlVersion::Version10,
+            encoding: "utf-8".to_string(),
+            standalone: None,
+            perform_indent: false,
+            indent_string: "  ".to_string(),
+            line_separator: "\n".to_string(),
+            self_closing_empty_elements: true,
+            pad_self_closing: true,
+        }
+    }
+
+    pub fn write_document_declaration(mut self, write_document_declaration: bool) -> Self {
+        self.write_document_declaration = write_document_declaration;
+        self
+    }
+
+    pub fn xml_version(mut self, xml_version: XmlVersion) -> Self {
+        self.xml_version = xml_version;
+        self
+    }
+
+    pub fn encoding(mut self, encoding: impl Into<String>) -> Self {
+        self.encoding = encoding.into();
+        self
+    }
+
+    /// The encoding that the document is declared in, which is UTF-8 if it has no declaration
+    pub(crate) fn declared_encoding(&self) -> &str {
+        if self.write_document_declaration {
+            &self.encoding
+        } else {
+            "utf-8"
+        }
+    }
+
+    pub fn standalone(mut self, standalone: Option<bool>) -> Self {
+        self.standalone = standalone;
+        self
+    }
+
+    pub fn perform_indent(mut self, perform_indent: bool) -> Self {
+        self.perform_indent = perform_indent;
+        self
+    }
+
+    pub fn indent_string(mut self, indent_string: impl Into<String>) -> Self {
+        self.indent_string = indent_string.into();
+        self
+    }
+
+    pub fn line_separator(mut self, line_separator: impl Into<String>) -> Self {
+        self.line_separator = line_separator.into();
+        self
+    }
+
+    pub fn self_closing_empty_elements(mut self, self_closing_empty_elements: bool) -> Self {
+        self.self_closing_empty_elements = self_closing_empty_elements;
+        self
+    }
+
+    pub fn pad_self_closing(mut self, pad_self_closing: bool) -> Self {
+        self.pad_self_closing = pad_self_closing;
+        self
+    }
+}
+
+impl Default for WriteOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
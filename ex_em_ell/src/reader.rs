@@ -0,0 +1,594 @@
+use std::borrow::Cow;
+use std::collections::{HashMap, VecDeque};
+use std::io::Read;
+use std::sync::{Arc, Weak};
+
+use crate::{
+    backend::{xml_rs::XmlRsSource, XmlBorrowedEventSource, XmlEventSource, XmlSourceError},
+    doctype::entity_declarations,
+    errors::{
+        DoctypeViolation, ElementPath, ElementPathSegment, ReadLimit, TextPosition, XmlReadError,
+    },
+    events::XmlEvent,
+    options::{DoctypePolicy, ReadOptions},
+    xml_utils::to_xml_read_error,
+};
+
+/// The source of events for [`FromXmlDocument`](crate::FromXmlDocument) and
+/// [`FromXmlElement`](crate::FromXmlElement), which carries the [`ReadOptions`] the document is
+/// being read with and tracks where in the document the reader is
+///
+/// The events are read from an [`XmlEventSource`], which is [`XmlRsSource`] unless another is
+/// given to [`from_source`](Self::from_source).
+///
+/// Readers that need to look ahead can [peek](Self::peek_event) at the next event, or
+/// [`attempt`](Self::attempt) to read something and go back if it fails, e.g. to try reading an
+/// element as something else. The events since the earliest [`mark`](Self::mark) that is still
+/// set are buffered, and replayed after rewinding.
+pub struct XmlEventReader<R: XmlEventSource> {
+    source: R,
+    options: ReadOptions,
+    position: TextPosition,
+    /// Events that have been read from the source ahead of the reader, or that were read while a
+    /// mark was set and may be read again
+    buffered: VecDeque<(XmlEvent, TextPosition)>,
+    /// The index in `buffered` of the next event, which is only past the start while a mark is set
+    cursor: usize,
+    /// The marks that have not been rewound to or released, in the order they were set
+    ///
+    /// A mark whose [`Mark`] has been dropped is forgotten once the marks set after it are.
+    marks: Vec<Weak<()>>,
+    path: ElementPath,
+    sibling_counts: Vec<HashMap<String, usize>>,
+    ended_element: bool,
+    element_count: usize,
+    checked_doctype: bool,
+    collected_errors: Option<Vec<XmlReadError>>,
+}
+
+impl<R: Read> XmlEventReader<XmlRsSource<R>> {
+    pub fn new(source: R) -> Self {
+        Self::new_with_options(source, ReadOptions::default())
+    }
+
+    pub fn new_with_options(source: R, options: ReadOptions) -> Self {
+        Self::from_source(XmlRsSource::new(source, &options), options)
+    }
+}
+
+impl<R: XmlEventSource> XmlEventReader<R> {
+    /// Read the events of `source`, which should have been configured with the same `options`
+    pub fn from_source(source: R, options: ReadOptions) -> Self {
+        Self {
+            position: source.position(),
+            source,
+            options,
+            buffered: VecDeque::new(),
+            cursor: 0,
+            marks: Vec::new(),
+            path: ElementPath::default(),
+            sibling_counts: vec![HashMap::new()],
+            ended_element: false,
+            element_count: 0,
+            checked_doctype: false,
+            collected_errors: None,
+        }
+    }
+
+    /// Read the next event, checking it against the limits in the [`ReadOptions`]
+    ///
+    /// Text is trimmed, and whitespace between elements skipped, if the options
+    /// [trim whitespace](ReadOptions::trim_whitespace).
+    pub fn next_event(&mut self) -> Result<XmlEvent, XmlReadError> {
+        loop {
+            let event = self.next_untrimmed_event()?;
+            if let Some(event) = self.trim_event(event, trim_string) {
+                return Ok(event);
+            }
+        }
+    }
+
+    /// Read the next event like [`next_event`](Self::next_event), with its text as it was
+    /// written, so that text split by comments or CDATA sections can be joined before it is
+    /// trimmed
+    pub(crate) fn next_untrimmed_event(&mut self) -> Result<XmlEvent, XmlReadError> {
+        self.leave_ended_element();
+        let (event, position) = match self.next_buffered() {
+            Some(buffered) => buffered,
+            None => {
+                let read = self.read_source_event(R::next_event)?;
+                self.keep_for_marks(|| read.clone());
+                read
+            }
+        };
+        self.track_event(&event, position)?;
+
+        Ok(event)
+    }
+
+    /// Trim the text of `event` with `trim` if the options ask for it, or skip it if it is only
+    /// whitespace
+    fn trim_event<S>(&self, event: XmlEvent<S>, trim: impl FnOnce(S) -> S) -> Option<XmlEvent<S>> {
+        if !self.options.trim_whitespace {
+            return Some(event);
+        }
+        match event {
+            XmlEvent::Whitespace(_) => None,
+            XmlEvent::Characters(text) => Some(XmlEvent::Characters(trim(text))),
+            event => Some(event),
+        }
+    }
+
+    /// Take the next event that has already been read from the source, keeping it while a mark is
+    /// set
+    fn next_buffered(&mut self) -> Option<(XmlEvent, TextPosition)> {
+        self.forget_dropped_marks();
+        if self.marks.is_empty() {
+            return self.buffered.pop_front();
+        }
+        let buffered = self.buffered.get(self.cursor).cloned()?;
+        self.cursor += 1;
+        Some(buffered)
+    }
+
+    /// Keep an event that has just been read from the source while a mark is set, so that it can
+    /// be read again after rewinding
+    fn keep_for_marks(&mut self, event: impl FnOnce() -> (XmlEvent, TextPosition)) {
+        if !self.marks.is_empty() {
+            self.buffered.push_back(event());
+            self.cursor += 1;
+        }
+    }
+
+    /// An element stays on the path until the event after its end tag, so that errors about an
+    /// element that has just ended (e.g. missing fields) still refer to it
+    fn leave_ended_element(&mut self) {
+        if self.ended_element {
+            self.path.segments.pop();
+            self.sibling_counts.pop();
+            self.ended_element = false;
+        }
+    }
+
+    /// Check the event that has just been read against the limits, and track the path to it
+    fn track_event<S: AsRef<str>>(
+        &mut self,
+        event: &XmlEvent<S>,
+        position: TextPosition,
+    ) -> Result<(), XmlReadError> {
+        self.position = position;
+        self.check_limits(event)?;
+
+        match event {
+            XmlEvent::StartElement { name, .. } => {
+                let name = name.prefixed();
+                let index = self
+                    .sibling_counts
+                    .last_mut()
+                    .map(|counts| {
+                        let count = counts.entry(name.clone()).or_default();
+                        *count += 1;
+                        *count
+                    })
+                    .unwrap_or(1);
+
+                self.path.segments.push(ElementPathSegment { name, index });
+                self.sibling_counts.push(HashMap::new());
+            }
+            XmlEvent::EndElement { .. } => {
+                self.ended_element = true;
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// Look at the next event without reading it, so that it is returned by the next call to
+    /// [`next_event`](Self::next_event)
+    ///
+    /// The event is only checked against the limits once it is read.
+    pub fn peek_event(&mut self) -> Result<XmlEvent, XmlReadError> {
+        let mut index = self.cursor;
+        loop {
+            if index == self.buffered.len() {
+                let read = self.read_source_event(R::next_event)?;
+                self.buffered.push_back(read);
+            }
+            if let Some(event) = self.trim_event(self.buffered[index].0.clone(), trim_string) {
+                return Ok(event);
+            }
+            index += 1;
+        }
+    }
+
+    /// Mark the current point in the document, so that the reader can be rewound to it
+    ///
+    /// Every event read after the earliest mark that is still set is buffered, until the mark is
+    /// passed to [`rewind`](Self::rewind) or [`release`](Self::release), or dropped. Marks can be
+    /// nested, but have to be rewound to or released in the reverse order they were set, which
+    /// [`attempt`](Self::attempt) takes care of.
+    pub fn mark(&mut self) -> Mark {
+        let token = Arc::new(());
+        self.marks.push(Arc::downgrade(&token));
+        Mark {
+            token,
+            cursor: self.cursor,
+            position: self.position,
+            path: self.path.clone(),
+            sibling_counts: self.sibling_counts.clone(),
+            ended_element: self.ended_element,
+            element_count: self.element_count,
+            collected_errors: self.collected_error_count(),
+        }
+    }
+
+    /// Go back to `mark`, so that the events read since it was set are read again
+    ///
+    /// The position and path are restored, and errors recorded since the mark are discarded.
+    /// Errors from the source itself, such as syntax errors, are not buffered and cannot be
+    /// rewound past.
+    ///
+    /// # Panics
+    ///
+    /// If a mark that was set after `mark` is still held, or `mark` was set on another reader.
+    pub fn rewind(&mut self, mark: Mark) {
+        self.pop_mark(&mark);
+        self.cursor = mark.cursor;
+        self.position = mark.position;
+        self.path = mark.path;
+        self.sibling_counts = mark.sibling_counts;
+        self.ended_element = mark.ended_element;
+        self.element_count = mark.element_count;
+        if let Some(errors) = &mut self.collected_errors {
+            errors.truncate(mark.collected_errors);
+        }
+        self.forget_dropped_marks();
+    }
+
+    /// Keep what has been read since `mark`, and stop buffering events for it
+    ///
+    /// # Panics
+    ///
+    /// Like [`rewind`](Self::rewind), if a mark that was set after `mark` is still held, or `mark`
+    /// was set on another reader.
+    pub fn release(&mut self, mark: Mark) {
+        self.pop_mark(&mark);
+        self.forget_dropped_marks();
+    }
+
+    fn pop_mark(&mut self, mark: &Mark) {
+        self.forget_dropped_marks();
+        let is_last = self
+            .marks
+            .last()
+            .is_some_and(|last| std::ptr::eq(last.as_ptr(), Arc::as_ptr(&mark.token)));
+        assert!(
+            is_last,
+            "marks have to be rewound to or released in the reverse order they were set"
+        );
+        self.marks.pop();
+    }
+
+    /// Forget the last marks if their [`Mark`] has been dropped, and stop buffering events once
+    /// no marks are left
+    fn forget_dropped_marks(&mut self) {
+        while self
+            .marks
+            .last()
+            .is_some_and(|last| last.strong_count() == 0)
+        {
+            self.marks.pop();
+        }
+        if self.marks.is_empty() {
+            self.buffered.drain(..self.cursor);
+            self.cursor = 0;
+        }
+    }
+
+    /// Read with `read`, rewinding to where it started if it fails so that the same events can be
+    /// read again, e.g. as something else
+    pub fn attempt<T>(
+        &mut self,
+        read: impl FnOnce(&mut Self) -> Result<T, XmlReadError>,
+    ) -> Result<T, XmlReadError> {
+        let mark = self.mark();
+        let result = read(self);
+        match result {
+            Ok(_) => self.release(mark),
+            Err(_) => self.rewind(mark),
+        }
+        result
+    }
+
+    fn read_source_event<S>(
+        &mut self,
+        read: impl FnOnce(&mut R) -> Result<XmlEvent<S>, XmlSourceError>,
+    ) -> Result<(XmlEvent<S>, TextPosition), XmlReadError> {
+        let event = read(&mut self.source);
+        let position = self.source.position();
+        // A DOCTYPE has no event of its own, so it is checked at the root element, which follows
+        // it. It is also checked before errors from the source are reported, as expanding
+        // entities that the policy does not allow is itself an error.
+        if matches!(event, Err(_) | Ok(XmlEvent::StartElement { .. })) {
+            self.check_doctype(position)?;
+        }
+        let event = event.map_err(|error| {
+            to_xml_read_error(self.current_element_name())(error)
+                .with_position(position)
+                .with_path(&self.path)
+        })?;
+        Ok((event, position))
+    }
+
+    fn check_doctype(&mut self, position: TextPosition) -> Result<(), XmlReadError> {
+        if self.checked_doctype {
+            return Ok(());
+        }
+        self.checked_doctype = true;
+        let Some(doctype) = self.source.doctype() else {
+            return Ok(());
+        };
+
+        let entities = entity_declarations(doctype);
+        let violation = match self.options.doctype {
+            DoctypePolicy::Reject => Some(DoctypeViolation::Doctype),
+            _ if entities.iter().any(|entity| entity.value.is_none()) => {
+                Some(DoctypeViolation::ExternalEntityDeclaration)
+            }
+            DoctypePolicy::AllowWithoutEntities if !entities.is_empty() => {
+                Some(DoctypeViolation::EntityDeclaration)
+            }
+            _ => None,
+        };
+
+        match violation {
+            Some(violation) => Err(XmlReadError::DoctypeNotAllowed {
+                violation,
+                position: Some(position),
+                path: Some(self.path.clone()),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    fn check_limits<S: AsRef<str>>(&mut self, event: &XmlEvent<S>) -> Result<(), XmlReadError> {
+        let options = &self.options;
+        let limit = match event {
+            XmlEvent::StartElement { attributes, .. } => {
+                self.element_count += 1;
+                if self.path.segments.len() >= options.max_depth {
+                    Some(ReadLimit::Depth(options.max_depth))
+                } else if self.element_count > options.max_elements {
+                    Some(ReadLimit::Elements(options.max_elements))
+                } else if attributes.len() > options.max_attributes {
+                    Some(ReadLimit::Attributes(options.max_attributes))
+                } else if attributes
+                    .iter()
+                    .any(|attribute| attribute.value.as_ref().len() > options.max_text_length)
+                {
+                    Some(ReadLimit::TextLength(options.max_text_length))
+                } else {
+                    None
+                }
+            }
+            XmlEvent::Characters(text)
+            | XmlEvent::CData(text)
+            | XmlEvent::Whitespace(text)
+            | XmlEvent::Comment(text)
+                if text.as_ref().len() > options.max_text_length =>
+            {
+                Some(ReadLimit::TextLength(options.max_text_length))
+            }
+            _ => None,
+        };
+
+        match limit {
+            Some(limit) => Err(self.limit_exceeded(limit)),
+            None => Ok(()),
+        }
+    }
+
+    /// Report that the document has exceeded `limit` at the last event that was read
+    pub fn limit_exceeded(&self, limit: ReadLimit) -> XmlReadError {
+        XmlReadError::LimitExceeded {
+            limit,
+            element: self.current_element_name(),
+            position: Some(self.position()),
+            path: Some(self.path.clone()),
+        }
+    }
+
+    fn current_element_name(&self) -> String {
+        self.path
+            .segments
+            .last()
+            .map_or_else(|| "document".to_string(), |segment| segment.name.clone())
+    }
+
+    /// The position of the last event that was read
+    pub fn position(&self) -> TextPosition {
+        self.position
+    }
+
+    /// The path of the element that contains the last event that was read, or of the element
+    /// itself for start and end tags
+    pub fn path(&self) -> &ElementPath {
+        &self.path
+    }
+
+    /// The number of elements that are open at the last event that was read
+    pub fn depth(&self) -> usize {
+        self.path.segments.len() - usize::from(self.ended_element)
+    }
+
+    /// Skip events until the element that was open at `depth` has ended
+    ///
+    /// Passing the [`depth`](Self::depth) just after reading a start tag skips that element's
+    /// whole subtree, including its end tag.
+    pub fn skip_element(&mut self, depth: usize) -> Result<(), XmlReadError> {
+        while self.depth() >= depth && depth > 0 {
+            self.next_event()?;
+        }
+        Ok(())
+    }
+
+    /// Skip the next event, along with the rest of its element if it is a start tag
+    pub fn skip_subtree(&mut self) -> Result<(), XmlReadError> {
+        if let XmlEvent::StartElement { .. } = self.next_event()? {
+            self.skip_element(self.depth())?;
+        }
+        Ok(())
+    }
+
+    /// Record recoverable errors instead of failing on them, so that every error in a document
+    /// can be reported in one pass
+    pub fn collect_errors(&mut self) {
+        self.collected_errors.get_or_insert_with(Vec::new);
+    }
+
+    pub fn is_collecting_errors(&self) -> bool {
+        self.collected_errors.is_some()
+    }
+
+    /// The number of errors that have been recorded so far
+    pub fn collected_error_count(&self) -> usize {
+        self.collected_errors.as_ref().map_or(0, Vec::len)
+    }
+
+    /// Take the errors that have been recorded so far
+    pub fn take_collected_errors(&mut self) -> Vec<XmlReadError> {
+        self.collected_errors
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    /// Record `error` if errors are being collected and it is recoverable, otherwise return it
+    pub fn record_error(&mut self, error: XmlReadError) -> Result<(), XmlReadError> {
+        match &mut self.collected_errors {
+            Some(errors) if error.is_recoverable() => {
+                errors.push(error.with_position(self.position).with_path(&self.path));
+                Ok(())
+            }
+            _ => Err(error),
+        }
+    }
+
+    /// Turn a recoverable error into `None` if errors are being collected, recording it
+    pub fn recover<T>(
+        &mut self,
+        result: Result<T, XmlReadError>,
+    ) -> Result<Option<T>, XmlReadError> {
+        match result {
+            Ok(value) => Ok(Some(value)),
+            Err(error) => self.record_error(error).map(|_| None),
+        }
+    }
+
+    /// Like [`recover`](Self::recover), but also skip the rest of the element that was open at
+    /// `depth` when the error was recorded
+    pub fn recover_element<T>(
+        &mut self,
+        result: Result<T, XmlReadError>,
+        depth: usize,
+    ) -> Result<Option<T>, XmlReadError> {
+        let value = self.recover(result)?;
+        if value.is_none() {
+            self.skip_element(depth)?;
+        }
+        Ok(value)
+    }
+
+    /// Fail if any errors were recorded after the first `count` errors
+    ///
+    /// This is called once an element has been fully read, so that an element with invalid
+    /// content is not itself reported as missing. The last error is handed back to the caller,
+    /// which records it again when it recovers.
+    pub fn check_collected_errors(&mut self, count: usize) -> Result<(), XmlReadError> {
+        match &mut self.collected_errors {
+            Some(errors) if errors.len() > count => Err(errors
+                .pop()
+                .expect("There should be an error after the first count errors")),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn options(&self) -> &ReadOptions {
+        &self.options
+    }
+
+    pub fn into_inner(self) -> R {
+        self.source
+    }
+}
+
+impl<'de, R: XmlBorrowedEventSource<'de>> XmlEventReader<R> {
+    /// Read the next event like [`next_event`](Self::next_event), but with its text borrowed
+    /// from the document where it did not need to be decoded
+    ///
+    /// An event that was [peeked](Self::peek_event), or that is read again after
+    /// [rewinding](Self::rewind), has already been copied.
+    pub fn next_borrowed_event(&mut self) -> Result<XmlEvent<Cow<'de, str>>, XmlReadError> {
+        loop {
+            let event = self.next_untrimmed_borrowed_event()?;
+            if let Some(event) = self.trim_event(event, trim_cow) {
+                return Ok(event);
+            }
+        }
+    }
+
+    /// Read the next event like [`next_untrimmed_event`](Self::next_untrimmed_event), with its
+    /// text borrowed like [`next_borrowed_event`](Self::next_borrowed_event)
+    pub(crate) fn next_untrimmed_borrowed_event(
+        &mut self,
+    ) -> Result<XmlEvent<Cow<'de, str>>, XmlReadError> {
+        self.leave_ended_element();
+        let (event, position) = match self.next_buffered() {
+            Some((event, position)) => (event.into(), position),
+            None => {
+                let read = self.read_source_event(R::next_borrowed_event)?;
+                self.keep_for_marks(|| (read.0.clone().into_owned(), read.1));
+                read
+            }
+        };
+        self.track_event(&event, position)?;
+
+        Ok(event)
+    }
+}
+
+pub(crate) fn trim_string(text: String) -> String {
+    let trimmed = text.trim();
+    if trimmed.len() == text.len() {
+        text
+    } else {
+        trimmed.to_string()
+    }
+}
+
+pub(crate) fn trim_cow(text: Cow<'_, str>) -> Cow<'_, str> {
+    match text {
+        Cow::Borrowed(text) => Cow::Borrowed(text.trim()),
+        Cow::Owned(text) => Cow::Owned(trim_string(text)),
+    }
+}
+
+/// A point in the document that an [`XmlEventReader`] can be rewound to, created by
+/// [`XmlEventReader::mark`]
+///
+/// Dropping a mark releases it, as [`XmlEventReader::release`] does.
+#[derive(Debug)]
+#[must_use = "a mark is released as soon as it is dropped"]
+pub struct Mark {
+    /// Identifies the mark in the reader's stack of marks, which only keeps a weak reference to
+    /// it so that it can tell whether the mark has been dropped
+    token: Arc<()>,
+    cursor: usize,
+    position: TextPosition,
+    path: ElementPath,
+    sibling_counts: Vec<HashMap<String, usize>>,
+    ended_element: bool,
+    element_count: usize,
+    collected_errors: usize,
+}
@@ -0,0 +1,172 @@
+use std::marker::PhantomData;
+
+use crate::{
+    backend::XmlEventSource,
+    errors::XmlReadError,
+    events::{Namespace, XmlAttribute, XmlEvent, XmlName},
+    reader::XmlEventReader,
+    xml_utils::{
+        is_child_element, is_misc_event, unexpected_event_depth,
+        unexpected_event_with_known_values_error,
+    },
+    FromXmlElement,
+};
+
+/// The start tag of an element that [`XmlEventReader::seek_element`] found
+#[derive(Clone, Debug, PartialEq)]
+pub struct StartElement {
+    pub name: XmlName,
+    pub attributes: Vec<XmlAttribute>,
+    pub namespace: Namespace,
+}
+
+/// An iterator over the repeated child elements of one element, which reads and yields them one
+/// at a time, created by [`XmlEventReader::stream_elements`]
+///
+/// The iterator ends after the end tag of the parent element, and the reader can then be used to
+/// read the rest of the document. An item that fails to read is yielded as an error, and if the
+/// error is recoverable the rest of that element is skipped so that the following items can still
+/// be read.
+pub struct ElementStream<'r, R: XmlEventSource, T> {
+    reader: &'r mut XmlEventReader<R>,
+    parent: Option<XmlName>,
+    element_tag: String,
+    item: PhantomData<T>,
+}
+
+impl<R: XmlEventSource> XmlEventReader<R> {
+    /// Read until the start tag of the element at `path`, e.g. `/bom/components`, skipping any
+    /// other elements along the way
+    ///
+    /// The path is matched against the names of elements as they are written in the document, in
+    /// the same form as [`ElementPath`](crate::errors::ElementPath). This returns `None` if the
+    /// document ends without such an element, or if the reader is already past it.
+    pub fn seek_element(&mut self, path: &str) -> Result<Option<StartElement>, XmlReadError> {
+        let target: Vec<&str> = path.split('/').filter(|name| !name.is_empty()).collect();
+
+        loop {
+            match self.next_event()? {
+                XmlEvent::StartElement {
+                    name,
+                    attributes,
+                    namespace,
+                } => {
+                    let depth = self.depth();
+                    let on_path = depth <= target.len()
+                        && self
+                            .path()
+                            .segments
+                            .iter()
+                            .zip(&target)
+                            .all(|(segment, name)| segment.name == *name);
+
+                    if !on_path {
+                        self.skip_element(depth)?;
+                    } else if depth == target.len() {
+                        return Ok(Some(StartElement {
+                            name,
+                            attributes,
+                            namespace,
+                        }));
+                    }
+                }
+                XmlEvent::EndDocument => return Ok(None),
+                _ => {}
+            }
+        }
+    }
+
+    /// Read the element at `path` as a `T`, skipping any other elements before it
+    ///
+    /// This can be used to read the fields of a document that come after a
+    /// [stream](Self::stream_elements) of elements.
+    pub fn read_element<T: FromXmlElement>(
+        &mut self,
+        path: &str,
+    ) -> Result<Option<T>, XmlReadError> {
+        match self.seek_element(path)? {
+            Some(element) => {
+                T::from_xml_element(self, &element.name, &element.attributes, &element.namespace)
+                    .map(Some)
+                    .map_err(|error| error.with_position(self.position()).with_path(self.path()))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Stream the `element_tag` children of the element at `path`, e.g. the `component` children
+    /// of `/bom/components`, reading each one as a `T` only when it is needed
+    ///
+    /// The stream is empty if there is no element at `path`.
+    pub fn stream_elements<T: FromXmlElement>(
+        &mut self,
+        path: &str,
+        element_tag: &str,
+    ) -> Result<ElementStream<'_, R, T>, XmlReadError> {
+        let parent = self.seek_element(path)?.map(|element| element.name);
+        Ok(ElementStream {
+            reader: self,
+            parent,
+            element_tag: element_tag.to_string(),
+            item: PhantomData,
+        })
+    }
+}
+
+impl<R: XmlEventSource, T: FromXmlElement> ElementStream<'_, R, T> {
+    fn read_item(&mut self, parent: &XmlName) -> Option<Result<T, XmlReadError>> {
+        loop {
+            let event = match self.reader.next_event() {
+                Ok(event) => event,
+                Err(error) => return Some(Err(error)),
+            };
+            let depth = unexpected_event_depth(self.reader, &event);
+
+            let result = match event {
+                XmlEvent::StartElement {
+                    name,
+                    attributes,
+                    namespace,
+                } if is_child_element(&name, parent, &self.element_tag) => {
+                    T::from_xml_element(self.reader, &name, &attributes, &namespace)
+                }
+                XmlEvent::EndElement { name } if &name == parent => return None,
+                event if is_misc_event(&event) => continue,
+                unexpected => Err(unexpected_event_with_known_values_error(
+                    self.reader,
+                    &parent.local_name,
+                    vec![self.element_tag.clone()],
+                    unexpected,
+                )),
+            };
+
+            return Some(result.map_err(|error| {
+                let error = error
+                    .with_position(self.reader.position())
+                    .with_path(self.reader.path());
+                if error.is_recoverable() {
+                    if let Err(skip_error) = self.reader.skip_element(depth) {
+                        return skip_error;
+                    }
+                }
+                error
+            }));
+        }
+    }
+}
+
+impl<R: XmlEventSource, T: FromXmlElement> Iterator for ElementStream<'_, R, T> {
+    type Item = Result<T, XmlReadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let parent = self.parent.take()?;
+        let item = self.read_item(&parent);
+        // The stream ends after the parent's end tag, or at an error that cannot be skipped
+        if matches!(&item, Some(Ok(_)))
+            || matches!(&item, Some(Err(error)) if error.is_recoverable())
+        {
+            self.parent = Some(parent);
+        }
+        item
+    }
+}
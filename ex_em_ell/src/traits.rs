@@ -1,25 +1,27 @@
-use std::io::{Read, Write};
-use xml::{
-    attribute::OwnedAttribute, name::OwnedName, namespace::Namespace, writer, EventReader,
-    EventWriter,
-};
+// The trait methods take `self: &Self`, as they always have
+#![allow(clippy::needless_arbitrary_self_type)]
+
+use std::borrow::Cow;
 
 use crate::{
+    backend::{XmlBorrowedEventSource, XmlEventSink, XmlEventSource},
     errors::{XmlReadError, XmlWriteError},
-    xml_utils::{read_list_tag, read_simple_tag, to_xml_write_error, write_simple_tag},
+    events::{Namespace, WriteEvent, XmlAttribute, XmlName},
+    reader::XmlEventReader,
+    xml_utils::{
+        parse_xml_text, parse_xml_text_borrowed, read_list_tag, read_list_tag_borrowed,
+        read_simple_tag, read_simple_tag_borrowed, write_event, write_simple_tag,
+    },
 };
 
 pub trait ToXmlDocument {
-    fn to_xml_document<W: Write>(
-        self: &Self,
-        writer: &mut EventWriter<W>,
-    ) -> Result<(), XmlWriteError>;
+    fn to_xml_document<W: XmlEventSink>(self: &Self, writer: &mut W) -> Result<(), XmlWriteError>;
 }
 
 pub trait ToXmlElement {
-    fn to_xml_element<W: Write>(
+    fn to_xml_element<W: XmlEventSink>(
         self: &Self,
-        writer: &mut EventWriter<W>,
+        writer: &mut W,
         tag: &str,
     ) -> Result<(), XmlWriteError>;
 
@@ -33,133 +35,207 @@ pub trait NamedXmlElement {
 }
 
 pub trait FromXmlDocument {
-    fn from_xml_document<R: Read>(reader: &mut EventReader<R>) -> Result<Self, XmlReadError>
+    fn from_xml_document<R: XmlEventSource>(
+        reader: &mut XmlEventReader<R>,
+    ) -> Result<Self, XmlReadError>
     where
         Self: Sized;
 }
 
 pub trait FromXmlElement {
-    fn from_xml_element<R: Read>(
-        reader: &mut EventReader<R>,
-        element_name: &OwnedName,
-        element_attributes: &[OwnedAttribute],
+    fn from_xml_element<R: XmlEventSource>(
+        reader: &mut XmlEventReader<R>,
+        element_name: &XmlName,
+        element_attributes: &[XmlAttribute],
         element_namespace: &Namespace,
     ) -> Result<Self, XmlReadError>
     where
         Self: Sized;
+
+    /// The value of a field whose element is missing, for types that can represent a missing
+    /// element, e.g. [`Nillable::Absent`](crate::Nillable::Absent)
+    fn from_missing_element() -> Option<Self>
+    where
+        Self: Sized,
+    {
+        None
+    }
 }
 
-impl ToXmlElement for String {
-    fn to_xml_element<W: Write>(
-        self: &Self,
-        writer: &mut EventWriter<W>,
-        tag: &str,
-    ) -> Result<(), XmlWriteError> {
-        write_simple_tag(writer, tag, self)
+/// Like [`FromXmlDocument`], for documents that borrow text from the input they are read from
+pub trait FromXmlDocumentBorrowed<'de>: Sized {
+    fn from_xml_document_borrowed<R: XmlBorrowedEventSource<'de>>(
+        reader: &mut XmlEventReader<R>,
+    ) -> Result<Self, XmlReadError>;
+}
+
+/// Like [`FromXmlElement`], for elements that borrow text from the input they are read from, e.g.
+/// through `&'de str` or `Cow<'de, str>` fields
+pub trait FromXmlElementBorrowed<'de>: Sized {
+    fn from_xml_element_borrowed<R: XmlBorrowedEventSource<'de>>(
+        reader: &mut XmlEventReader<R>,
+        element_name: &XmlName,
+        element_attributes: &[XmlAttribute<Cow<'de, str>>],
+        element_namespace: &Namespace,
+    ) -> Result<Self, XmlReadError>;
+
+    /// Like [`FromXmlElement::from_missing_element`]
+    fn from_missing_element() -> Option<Self> {
+        None
     }
 }
 
-impl FromXmlElement for String {
-    fn from_xml_element<R: Read>(
-        reader: &mut EventReader<R>,
-        element_name: &OwnedName,
-        _element_attributes: &[OwnedAttribute],
-        _element_namespace: &Namespace,
-    ) -> Result<Self, XmlReadError>
-    where
-        Self: Sized,
-    {
-        read_simple_tag(reader, element_name)
+/// A value that is read from text which may be borrowed from the input
+///
+/// Every [`XmlSimpleType`] is read this way, as are `&str`, which fails for text that had to be
+/// decoded, and `Cow<str>`, which only copies text that had to be decoded. Implementing this trait
+/// provides [`FromXmlElementBorrowed`] for the type, and allows it to be used in attribute fields
+/// of derived borrowed types.
+pub trait FromXmlTextBorrowed<'de>: Sized {
+    /// The XSD type name used when reporting values that fail to parse, e.g. `xs:integer`
+    fn xml_type_name() -> &'static str;
+
+    fn from_xml_text_borrowed(text: Cow<'de, str>) -> Option<Self>;
+
+    /// Whether the value can only be read from text that is borrowed from the document, so that
+    /// text that had to be decoded is reported as such rather than as an invalid value
+    fn requires_borrowed_text() -> bool {
+        false
     }
 }
 
-impl ToXmlElement for bool {
-    fn to_xml_element<W: Write>(
-        self: &Self,
-        writer: &mut EventWriter<W>,
+/// A value that is represented entirely as text, such as `xs:string` or `xs:integer`
+///
+/// Implementing this trait provides [`ToXmlElement`] and [`FromXmlElement`] for the type, and
+/// allows it to be used in attribute fields of derived types.
+pub trait XmlSimpleType {
+    /// The XSD type name used when reporting values that fail to parse, e.g. `xs:integer`
+    fn xml_type_name() -> &'static str;
+
+    fn to_xml_text(&self) -> String;
+
+    fn from_xml_text(text: &str) -> Option<Self>
+    where
+        Self: Sized;
+}
+
+impl<T> ToXmlElement for T
+where
+    T: XmlSimpleType,
+{
+    fn to_xml_element<W: XmlEventSink>(
+        &self,
+        writer: &mut W,
         tag: &str,
     ) -> Result<(), XmlWriteError> {
-        write_simple_tag(writer, tag, &self.to_string())
+        write_simple_tag(writer, tag, &self.to_xml_text())
     }
 }
 
-impl FromXmlElement for bool {
-    fn from_xml_element<R: Read>(
-        reader: &mut EventReader<R>,
-        element_name: &OwnedName,
-        _element_attributes: &[OwnedAttribute],
+impl<T> FromXmlElement for T
+where
+    T: XmlSimpleType,
+{
+    fn from_xml_element<R: XmlEventSource>(
+        reader: &mut XmlEventReader<R>,
+        element_name: &XmlName,
+        _element_attributes: &[XmlAttribute],
         _element_namespace: &Namespace,
     ) -> Result<Self, XmlReadError>
     where
         Self: Sized,
     {
+        let start_position = reader.position();
         read_simple_tag(reader, element_name).and_then(|value| {
-            let value = value.as_ref();
-            match value {
-                "true" | "1" => Ok(true),
-                "false" | "0" => Ok(false),
-                _ => Err(XmlReadError::InvalidParseError {
-                    value: value.to_string(),
-                    data_type: "xs:boolean".to_string(),
-                    element: element_name.to_string(),
-                }),
-            }
+            parse_xml_text(&value, element_name.to_string())
+                .map_err(|error| error.with_position(start_position).with_path(reader.path()))
         })
     }
 }
 
-impl ToXmlElement for u32 {
-    fn to_xml_element<W: Write>(
-        self: &Self,
-        writer: &mut EventWriter<W>,
-        tag: &str,
-    ) -> Result<(), XmlWriteError> {
-        write_simple_tag(writer, tag, &self.to_string())
+impl XmlSimpleType for String {
+    fn xml_type_name() -> &'static str {
+        "xs:string"
+    }
+
+    fn to_xml_text(&self) -> String {
+        self.clone()
+    }
+
+    fn from_xml_text(text: &str) -> Option<Self> {
+        Some(text.to_string())
     }
 }
 
-impl FromXmlElement for u32 {
-    fn from_xml_element<R: Read>(
-        reader: &mut EventReader<R>,
-        element_name: &OwnedName,
-        _element_attributes: &[OwnedAttribute],
-        _element_namespace: &Namespace,
-    ) -> Result<Self, XmlReadError>
-    where
-        Self: Sized,
-    {
-        read_simple_tag(reader, element_name).and_then(|value| {
-            value.parse().map_err(|_| XmlReadError::InvalidParseError {
-                value: value.to_string(),
-                data_type: "xs:integer".to_string(),
-                element: element_name.to_string(),
-            })
-        })
+impl XmlSimpleType for bool {
+    fn xml_type_name() -> &'static str {
+        "xs:boolean"
+    }
+
+    fn to_xml_text(&self) -> String {
+        self.to_string()
+    }
+
+    fn from_xml_text(text: &str) -> Option<Self> {
+        match text {
+            "true" | "1" => Some(true),
+            "false" | "0" => Some(false),
+            _ => None,
+        }
     }
 }
 
+macro_rules! impl_xml_simple_type_from_str {
+    ($($t:ty => $xml_type_name:literal),* $(,)?) => {
+        $(
+            impl XmlSimpleType for $t {
+                fn xml_type_name() -> &'static str {
+                    $xml_type_name
+                }
+
+                fn to_xml_text(&self) -> String {
+                    self.to_string()
+                }
+
+                fn from_xml_text(text: &str) -> Option<Self> {
+                    text.parse().ok()
+                }
+            }
+        )*
+    };
+}
+
+impl_xml_simple_type_from_str!(
+    u8 => "xs:unsignedByte",
+    u16 => "xs:unsignedShort",
+    u32 => "xs:unsignedInt",
+    u64 => "xs:unsignedLong",
+    usize => "xs:nonNegativeInteger",
+    i8 => "xs:byte",
+    i16 => "xs:short",
+    i32 => "xs:int",
+    i64 => "xs:long",
+    isize => "xs:integer",
+    f32 => "xs:float",
+    f64 => "xs:double",
+);
+
 impl<T> ToXmlElement for Vec<T>
 where
     T: ToXmlElement + NamedXmlElement,
 {
-    fn to_xml_element<W: Write>(
+    fn to_xml_element<W: XmlEventSink>(
         self: &Self,
-        writer: &mut EventWriter<W>,
+        writer: &mut W,
         tag: &str,
     ) -> Result<(), XmlWriteError> {
-        writer
-            .write(writer::XmlEvent::start_element(tag))
-            .map_err(to_xml_write_error(tag))?;
+        write_event(writer, WriteEvent::start_element(tag), tag)?;
 
         for element in self {
             element.to_xml_element(writer, T::xml_element_name())?;
         }
 
-        writer
-            .write(writer::XmlEvent::end_element())
-            .map_err(to_xml_write_error(tag))?;
-
-        Ok(())
+        write_event(writer, WriteEvent::end_element(), tag)
     }
 
     fn will_write(self: &Self) -> bool {
@@ -171,10 +247,10 @@ impl<T> FromXmlElement for Vec<T>
 where
     T: FromXmlElement + NamedXmlElement,
 {
-    fn from_xml_element<R: Read>(
-        reader: &mut EventReader<R>,
-        element_name: &OwnedName,
-        _element_attributes: &[OwnedAttribute],
+    fn from_xml_element<R: XmlEventSource>(
+        reader: &mut XmlEventReader<R>,
+        element_name: &XmlName,
+        _element_attributes: &[XmlAttribute],
         _element_namespace: &Namespace,
     ) -> Result<Self, XmlReadError>
     where
@@ -183,3 +259,75 @@ where
         read_list_tag(reader, element_name, T::xml_element_name())
     }
 }
+
+impl<'de, T> FromXmlTextBorrowed<'de> for T
+where
+    T: XmlSimpleType,
+{
+    fn xml_type_name() -> &'static str {
+        <T as XmlSimpleType>::xml_type_name()
+    }
+
+    fn from_xml_text_borrowed(text: Cow<'de, str>) -> Option<Self> {
+        T::from_xml_text(&text)
+    }
+}
+
+impl<'de: 'a, 'a> FromXmlTextBorrowed<'de> for &'a str {
+    fn xml_type_name() -> &'static str {
+        "xs:string"
+    }
+
+    fn from_xml_text_borrowed(text: Cow<'de, str>) -> Option<Self> {
+        match text {
+            Cow::Borrowed(text) => Some(text),
+            Cow::Owned(_) => None,
+        }
+    }
+
+    fn requires_borrowed_text() -> bool {
+        true
+    }
+}
+
+impl<'de: 'a, 'a> FromXmlTextBorrowed<'de> for Cow<'a, str> {
+    fn xml_type_name() -> &'static str {
+        "xs:string"
+    }
+
+    fn from_xml_text_borrowed(text: Cow<'de, str>) -> Option<Self> {
+        Some(text)
+    }
+}
+
+impl<'de, T> FromXmlElementBorrowed<'de> for T
+where
+    T: FromXmlTextBorrowed<'de>,
+{
+    fn from_xml_element_borrowed<R: XmlBorrowedEventSource<'de>>(
+        reader: &mut XmlEventReader<R>,
+        element_name: &XmlName,
+        _element_attributes: &[XmlAttribute<Cow<'de, str>>],
+        _element_namespace: &Namespace,
+    ) -> Result<Self, XmlReadError> {
+        let start_position = reader.position();
+        read_simple_tag_borrowed(reader, element_name).and_then(|value| {
+            parse_xml_text_borrowed(value, element_name.to_string())
+                .map_err(|error| error.with_position(start_position).with_path(reader.path()))
+        })
+    }
+}
+
+impl<'de, T> FromXmlElementBorrowed<'de> for Vec<T>
+where
+    T: FromXmlElementBorrowed<'de> + NamedXmlElement,
+{
+    fn from_xml_element_borrowed<R: XmlBorrowedEventSource<'de>>(
+        reader: &mut XmlEventReader<R>,
+        element_name: &XmlName,
+        _element_attributes: &[XmlAttribute<Cow<'de, str>>],
+        _element_namespace: &Namespace,
+    ) -> Result<Self, XmlReadError> {
+        read_list_tag_borrowed(reader, element_name, T::xml_element_name())
+    }
+}
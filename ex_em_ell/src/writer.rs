@@ -0,0 +1,159 @@
+use std::io::Write;
+
+use crate::{
+    backend::{xml_rs::XmlRsSink, XmlEventSink},
+    errors::XmlWriteError,
+    events::{StartElementBuilder, WriteEvent},
+    options::WriteOptions,
+    xml_utils::write_event,
+    ToXmlElement,
+};
+
+/// A document that is written one element at a time, so that large lists can be written
+/// without holding them in memory
+///
+/// Elements are opened with [`start_element`](Self::start_element) and closed with
+/// [`end_element`](Self::end_element), and any [`ToXmlElement`] can be written between them.
+/// [`finish`](Self::finish) checks that every element has been closed, while dropping the writer
+/// closes the elements that are still open, so that the document is well-formed if nothing failed
+/// to be written.
+pub struct XmlDocumentWriter<W: XmlEventSink> {
+    writer: Option<W>,
+    open_elements: Vec<String>,
+}
+
+impl<W: Write> XmlDocumentWriter<XmlRsSink<W>> {
+    pub fn new(writer: W) -> Result<Self, XmlWriteError> {
+        Self::new_with_options(writer, WriteOptions::default())
+    }
+
+    /// Start a document, writing its declaration if the options ask for one
+    pub fn new_with_options(writer: W, options: WriteOptions) -> Result<Self, XmlWriteError> {
+        Self::from_sink(XmlRsSink::new(writer, &options), &options)
+    }
+}
+
+impl<W: XmlEventSink> XmlDocumentWriter<W> {
+    /// Start a document written to `sink`, which should have been configured with the same
+    /// `options`, writing its declaration if the options ask for one
+    pub fn from_sink(mut sink: W, options: &WriteOptions) -> Result<Self, XmlWriteError> {
+        write_declaration(&mut sink, options)?;
+
+        Ok(Self {
+            writer: Some(sink),
+            open_elements: Vec::new(),
+        })
+    }
+
+    /// Open the element `tag`, which stays open until the matching [`end_element`](Self::end_element)
+    pub fn start_element(&mut self, tag: &str) -> Result<(), XmlWriteError> {
+        self.start_element_with(WriteEvent::start_element(tag))
+    }
+
+    /// Open the element `tag` with `namespace` as its default namespace, e.g. for the root element
+    pub fn start_element_with_namespace(
+        &mut self,
+        tag: &str,
+        namespace: &str,
+    ) -> Result<(), XmlWriteError> {
+        self.start_element_with(WriteEvent::start_element(tag).default_ns(namespace))
+    }
+
+    /// Open an element with the attributes and namespaces of `element`, e.g.
+    /// `WriteEvent::start_element("bom").attr("serialNumber", serial_number)`
+    pub fn start_element_with(
+        &mut self,
+        element: StartElementBuilder<'_>,
+    ) -> Result<(), XmlWriteError> {
+        let tag = element.name().to_string();
+        self.write(|writer| write_event(writer, element, &tag))?;
+        self.open_elements.push(tag);
+        Ok(())
+    }
+
+    /// Close the element that was opened last
+    pub fn end_element(&mut self) -> Result<(), XmlWriteError> {
+        let tag = self
+            .open_elements
+            .pop()
+            .ok_or(XmlWriteError::NoOpenElement)?;
+        self.write(|writer| write_event(writer, WriteEvent::end_element(), &tag))
+    }
+
+    /// Write `value` as the element `tag` inside the element that is currently open
+    pub fn write_element<T: ToXmlElement>(
+        &mut self,
+        value: &T,
+        tag: &str,
+    ) -> Result<(), XmlWriteError> {
+        if self.open_elements.is_empty() {
+            return Err(XmlWriteError::NoOpenElement);
+        }
+        self.write(|writer| value.to_xml_element(writer, tag))
+    }
+
+    /// The names of the elements that are currently open, starting with the root element
+    pub fn open_elements(&self) -> &[String] {
+        &self.open_elements
+    }
+
+    /// Finish the document, checking that every element has been closed, and return the
+    /// underlying writer
+    pub fn finish(mut self) -> Result<W::Inner, XmlWriteError> {
+        if !self.open_elements.is_empty() {
+            let elements = std::mem::take(&mut self.open_elements);
+            return Err(XmlWriteError::UnclosedElements { elements });
+        }
+
+        let writer = self
+            .writer
+            .take()
+            .expect("The writer is only taken when finishing");
+        Ok(writer.into_inner())
+    }
+
+    fn write(
+        &mut self,
+        write: impl FnOnce(&mut W) -> Result<(), XmlWriteError>,
+    ) -> Result<(), XmlWriteError> {
+        let writer = self
+            .writer
+            .as_mut()
+            .expect("The writer is only taken when finishing");
+        write(writer)
+    }
+}
+
+impl<W: XmlEventSink> Drop for XmlDocumentWriter<W> {
+    fn drop(&mut self) {
+        // Errors cannot be returned from here, so closing stops at the first one
+        let Some(writer) = self.writer.as_mut() else {
+            return;
+        };
+        while let Some(tag) = self.open_elements.pop() {
+            if write_event(writer, WriteEvent::end_element(), &tag).is_err() {
+                break;
+            }
+        }
+    }
+}
+
+/// Write the XML declaration, if `options` asks for one
+pub(crate) fn write_declaration<W: XmlEventSink>(
+    writer: &mut W,
+    options: &WriteOptions,
+) -> Result<(), XmlWriteError> {
+    if !options.write_document_declaration {
+        return Ok(());
+    }
+
+    write_event(
+        writer,
+        WriteEvent::StartDocument {
+            version: options.xml_version,
+            encoding: &options.encoding,
+            standalone: options.standalone,
+        },
+        "document declaration",
+    )
+}
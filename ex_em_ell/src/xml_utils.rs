@@ -1,151 +1,938 @@
-use itertools::Itertools;
-use std::io::{Read, Write};
+use std::borrow::Cow;
 
-use xml::{name::OwnedName, reader, writer, EventReader, EventWriter};
+use itertools::Itertools;
 
 use crate::{
-    errors::{XmlReadError, XmlWriteError},
-    FromXmlElement,
+    backend::{XmlBorrowedEventSource, XmlEventSink, XmlEventSource, XmlSinkError, XmlSourceError},
+    errors::{ReadLimit, XmlEventKind, XmlReadError, XmlWriteError},
+    events::{Namespace, WriteEvent, XmlAttribute, XmlEvent, XmlName},
+    options::XmlVersion,
+    reader::{trim_cow, trim_string, XmlEventReader},
+    FromXmlElement, FromXmlElementBorrowed, FromXmlTextBorrowed, ToXmlElement, XmlSimpleType,
 };
 
+/// The namespace of the `xsi:` attributes, e.g. `xsi:type`
+pub const XSI_NAMESPACE: &str = "http://www.w3.org/2001/XMLSchema-instance";
+
 /// Write a tag that is of the form `<tag>content</tag>`
-pub fn write_simple_tag<W: Write>(
-    writer: &mut EventWriter<W>,
+pub fn write_simple_tag<W: XmlEventSink>(
+    writer: &mut W,
     tag: &str,
     content: &str,
 ) -> Result<(), XmlWriteError> {
-    writer
-        .write(writer::XmlEvent::start_element(tag))
-        .map_err(to_xml_write_error(tag))?;
+    write_event(writer, WriteEvent::start_element(tag), tag)?;
 
-    writer
-        .write(writer::XmlEvent::characters(content))
-        .map_err(to_xml_write_error(tag))?;
+    write_event(writer, WriteEvent::characters(content), tag)?;
 
-    writer
-        .write(writer::XmlEvent::end_element())
-        .map_err(to_xml_write_error(tag))?;
-    Ok(())
+    write_event(writer, WriteEvent::end_element(), tag)
+}
+
+/// Write a tag that is of the form `<tag>value value value</tag>`, representing an `xs:list`
+pub fn write_xml_list_tag<W: XmlEventSink, T: XmlSimpleType>(
+    writer: &mut W,
+    tag: &str,
+    values: &[T],
+) -> Result<(), XmlWriteError> {
+    write_simple_tag(writer, tag, &to_xml_list_text(values))
 }
 
-pub fn read_simple_tag<R: Read>(
-    event_reader: &mut EventReader<R>,
-    element: &OwnedName,
+/// Read the text content of a tag that is of the form `<tag>content</tag>`
+///
+/// Empty tags (`<tag/>` or `<tag></tag>`) are read as an empty string. Text that is split across
+/// several events, e.g. around a comment or a CDATA section, is concatenated, and comments and
+/// processing instructions are skipped. If the options
+/// [trim whitespace](crate::ReadOptions::trim_whitespace), the text is trimmed once it has been
+/// concatenated, so whitespace between the pieces is kept.
+pub fn read_simple_tag<R: XmlEventSource>(
+    event_reader: &mut XmlEventReader<R>,
+    element: &XmlName,
 ) -> Result<String, XmlReadError> {
     let element_display = element.to_string();
-    let content = event_reader
-        .next()
-        .map_err(to_xml_read_error(&element_display))
-        .and_then(inner_text_or_error(&element_display))?;
+    let mut content = String::new();
 
-    event_reader
-        .next()
-        .map_err(to_xml_read_error(&element_display))
-        .and_then(closing_tag_or_error(element))?;
+    loop {
+        let next_element = event_reader.next_untrimmed_event()?;
+        match next_element {
+            XmlEvent::Characters(s) | XmlEvent::CData(s) | XmlEvent::Whitespace(s) => {
+                content.push_str(&s);
+                let max_text_length = event_reader.options().max_text_length;
+                if content.len() > max_text_length {
+                    return Err(event_reader.limit_exceeded(ReadLimit::TextLength(max_text_length)));
+                }
+            }
+            XmlEvent::Comment(_) | XmlEvent::ProcessingInstruction { .. } => {}
+            XmlEvent::EndElement { name } if &name == element => {
+                return Ok(if event_reader.options().trim_whitespace {
+                    trim_string(content)
+                } else {
+                    content
+                })
+            }
+            unexpected => {
+                return Err(unexpected_event_error(
+                    event_reader,
+                    &element_display,
+                    unexpected,
+                ))
+            }
+        }
+    }
+}
+
+/// Read the text content of a tag like [`read_simple_tag`], borrowing it from the document when
+/// it is a single piece of text that did not need to be decoded
+pub fn read_simple_tag_borrowed<'de, R: XmlBorrowedEventSource<'de>>(
+    event_reader: &mut XmlEventReader<R>,
+    element: &XmlName,
+) -> Result<Cow<'de, str>, XmlReadError> {
+    let element_display = element.to_string();
+    let mut content = Cow::Borrowed("");
+
+    loop {
+        let next_element = event_reader.next_untrimmed_borrowed_event()?;
+        match next_element {
+            XmlEvent::Characters(s) | XmlEvent::CData(s) | XmlEvent::Whitespace(s) => {
+                if content.is_empty() {
+                    content = s;
+                } else {
+                    content.to_mut().push_str(&s);
+                }
+                let max_text_length = event_reader.options().max_text_length;
+                if content.len() > max_text_length {
+                    return Err(event_reader.limit_exceeded(ReadLimit::TextLength(max_text_length)));
+                }
+            }
+            XmlEvent::Comment(_) | XmlEvent::ProcessingInstruction { .. } => {}
+            XmlEvent::EndElement { name } if &name == element => {
+                return Ok(if event_reader.options().trim_whitespace {
+                    trim_cow(content)
+                } else {
+                    content
+                })
+            }
+            unexpected => {
+                return Err(unexpected_event_error(
+                    event_reader,
+                    &element_display,
+                    unexpected,
+                ))
+            }
+        }
+    }
+}
+
+/// Parse the text of an element or attribute as a simple type
+pub fn parse_xml_text<T: XmlSimpleType>(
+    value: &str,
+    element: impl AsRef<str>,
+) -> Result<T, XmlReadError> {
+    T::from_xml_text(value).ok_or_else(|| XmlReadError::InvalidParseError {
+        value: value.to_string(),
+        data_type: T::xml_type_name().to_string(),
+        element: element.as_ref().to_string(),
+        position: None,
+        path: None,
+    })
+}
+
+/// Parse text that may be borrowed from the document
+pub fn parse_xml_text_borrowed<'de, T: FromXmlTextBorrowed<'de>>(
+    value: Cow<'de, str>,
+    element: impl AsRef<str>,
+) -> Result<T, XmlReadError> {
+    // Cloning borrowed text is free, so only text that was decoded is copied
+    let text = value.clone();
+    T::from_xml_text_borrowed(value).ok_or_else(|| match text {
+        Cow::Owned(text) if T::requires_borrowed_text() => XmlReadError::custom(
+            element.as_ref(),
+            format!(
+                "{:?} had to be decoded, so it cannot be borrowed as {}",
+                text,
+                T::xml_type_name()
+            ),
+        ),
+        text => XmlReadError::InvalidParseError {
+            value: text.into_owned(),
+            data_type: T::xml_type_name().to_string(),
+            element: element.as_ref().to_string(),
+            position: None,
+            path: None,
+        },
+    })
+}
+
+/// Read an attribute of the form `<element attribute="value">`, if it is present
+pub fn read_attribute<R: XmlEventSource, T: XmlSimpleType, S: AsRef<str>>(
+    event_reader: &XmlEventReader<R>,
+    element_attributes: &[XmlAttribute<S>],
+    attribute_name: &str,
+    element: &XmlName,
+) -> Result<Option<T>, XmlReadError> {
+    element_attributes
+        .iter()
+        .find(|attribute| is_unqualified_attribute(attribute, attribute_name))
+        .map(|attribute| {
+            parse_xml_text(
+                attribute.value.as_ref(),
+                format!("{}/@{}", element, attribute_name),
+            )
+            .map_err(|error| {
+                error
+                    .with_position(event_reader.position())
+                    .with_path(event_reader.path())
+            })
+        })
+        .transpose()
+}
+
+/// Read an attribute like [`read_attribute`], borrowing its value from the document where it can
+pub fn read_attribute_borrowed<'de, R: XmlEventSource, T: FromXmlTextBorrowed<'de>>(
+    event_reader: &XmlEventReader<R>,
+    element_attributes: &[XmlAttribute<Cow<'de, str>>],
+    attribute_name: &str,
+    element: &XmlName,
+) -> Result<Option<T>, XmlReadError> {
+    element_attributes
+        .iter()
+        .find(|attribute| is_unqualified_attribute(attribute, attribute_name))
+        .map(|attribute| {
+            parse_xml_text_borrowed(
+                attribute.value.clone(),
+                format!("{}/@{}", element, attribute_name),
+            )
+            .map_err(|error| {
+                error
+                    .with_position(event_reader.position())
+                    .with_path(event_reader.path())
+            })
+        })
+        .transpose()
+}
+
+/// Read a tag that is of the form `<tag>value value value</tag>`, representing an `xs:list`
+pub fn read_xml_list_tag<R: XmlEventSource, T: XmlSimpleType>(
+    event_reader: &mut XmlEventReader<R>,
+    element: &XmlName,
+) -> Result<Vec<T>, XmlReadError> {
+    let start_position = event_reader.position();
+    read_simple_tag(event_reader, element).and_then(|value| {
+        parse_xml_list(&value, element.to_string()).map_err(|error| {
+            error
+                .with_position(start_position)
+                .with_path(event_reader.path())
+        })
+    })
+}
+
+/// Read an attribute of the form `<element attribute="value value value">`, if it is present
+pub fn read_xml_list_attribute<R: XmlEventSource, T: XmlSimpleType, S: AsRef<str>>(
+    event_reader: &XmlEventReader<R>,
+    element_attributes: &[XmlAttribute<S>],
+    attribute_name: &str,
+    element: &XmlName,
+) -> Result<Option<Vec<T>>, XmlReadError> {
+    element_attributes
+        .iter()
+        .find(|attribute| is_unqualified_attribute(attribute, attribute_name))
+        .map(|attribute| {
+            parse_xml_list(
+                attribute.value.as_ref(),
+                format!("{}/@{}", element, attribute_name),
+            )
+            .map_err(|error| {
+                error
+                    .with_position(event_reader.position())
+                    .with_path(event_reader.path())
+            })
+        })
+        .transpose()
+}
+
+/// Parse whitespace-separated text as an `xs:list`, reporting the first value that fails to parse
+/// along with where it is in the list, e.g. `item 3 of sizes`
+pub fn parse_xml_list<T: XmlSimpleType>(
+    value: &str,
+    element: impl AsRef<str>,
+) -> Result<Vec<T>, XmlReadError> {
+    value
+        .split_ascii_whitespace()
+        .enumerate()
+        .map(|(index, item)| {
+            T::from_xml_text(item).ok_or_else(|| XmlReadError::InvalidParseError {
+                value: item.to_string(),
+                data_type: T::xml_type_name().to_string(),
+                element: format!("item {} of {}", index + 1, element.as_ref()),
+                position: None,
+                path: None,
+            })
+        })
+        .collect()
+}
 
-    Ok(content)
+/// Convert values to the whitespace-separated text of an `xs:list`
+pub fn to_xml_list_text<T: XmlSimpleType>(values: &[T]) -> String {
+    values.iter().map(XmlSimpleType::to_xml_text).join(" ")
 }
 
-pub fn read_list_tag<R: Read, T: FromXmlElement>(
-    event_reader: &mut EventReader<R>,
-    element_name: &OwnedName,
+pub fn read_list_tag<R: XmlEventSource, T: FromXmlElement>(
+    event_reader: &mut XmlEventReader<R>,
+    element_name: &XmlName,
     inner_element_tag: &str,
 ) -> Result<Vec<T>, XmlReadError> {
+    let collected_errors = event_reader.collected_error_count();
     let mut items = Vec::new();
 
     let mut got_end_tag = false;
     while !got_end_tag {
-        let next_element = event_reader
-            .next()
-            .map_err(to_xml_read_error(&element_name.local_name))?;
+        let next_element = event_reader.next_event()?;
         match next_element {
-            reader::XmlEvent::StartElement {
+            XmlEvent::StartElement {
                 name,
                 attributes,
                 namespace,
                 ..
-            } if name.local_name == inner_element_tag => {
-                items.push(T::from_xml_element(
+            } if is_child_element(&name, element_name, inner_element_tag) => {
+                let depth = event_reader.depth();
+                let item = T::from_xml_element(event_reader, &name, &attributes, &namespace);
+                if let Some(item) = event_reader.recover_element(item, depth)? {
+                    items.push(item);
+                }
+            }
+            XmlEvent::EndElement { name } if &name == element_name => {
+                got_end_tag = true;
+            }
+            event if is_misc_event(&event) => {}
+            unexpected => {
+                let depth = unexpected_event_depth(event_reader, &unexpected);
+                let error = unexpected_event_with_known_values_error(
                     event_reader,
-                    &name,
-                    &attributes,
-                    &namespace,
-                )?);
+                    element_name,
+                    vec![inner_element_tag.to_string()],
+                    unexpected,
+                );
+                event_reader.recover_element::<()>(Err(error), depth)?;
+            }
+        }
+    }
+    event_reader.check_collected_errors(collected_errors)?;
+
+    Ok(items)
+}
+
+/// Read a list of elements like [`read_list_tag`], for items that borrow from the document
+pub fn read_list_tag_borrowed<
+    'de,
+    R: XmlBorrowedEventSource<'de>,
+    T: FromXmlElementBorrowed<'de>,
+>(
+    event_reader: &mut XmlEventReader<R>,
+    element_name: &XmlName,
+    inner_element_tag: &str,
+) -> Result<Vec<T>, XmlReadError> {
+    let collected_errors = event_reader.collected_error_count();
+    let mut items = Vec::new();
+
+    let mut got_end_tag = false;
+    while !got_end_tag {
+        let next_element = event_reader.next_borrowed_event()?;
+        match next_element {
+            XmlEvent::StartElement {
+                name,
+                attributes,
+                namespace,
+                ..
+            } if is_child_element(&name, element_name, inner_element_tag) => {
+                let depth = event_reader.depth();
+                let item =
+                    T::from_xml_element_borrowed(event_reader, &name, &attributes, &namespace);
+                if let Some(item) = event_reader.recover_element(item, depth)? {
+                    items.push(item);
+                }
             }
-            reader::XmlEvent::EndElement { name } if &name == element_name => {
+            XmlEvent::EndElement { name } if &name == element_name => {
                 got_end_tag = true;
             }
+            event if is_misc_event(&event) => {}
             unexpected => {
-                return Err(unexpected_element_with_known_values_error(
+                let depth = unexpected_event_depth(event_reader, &unexpected);
+                let error = unexpected_event_with_known_values_error(
+                    event_reader,
                     element_name,
                     vec![inner_element_tag.to_string()],
                     unexpected,
-                ))
+                );
+                event_reader.recover_element::<()>(Err(error), depth)?;
             }
         }
     }
+    event_reader.check_collected_errors(collected_errors)?;
 
     Ok(items)
 }
 
-pub fn inner_text_or_error(
-    element_name: impl AsRef<str>,
-) -> impl FnOnce(xml::reader::XmlEvent) -> Result<String, XmlReadError> {
-    let element_name = element_name.as_ref().to_owned();
-    |event| match event {
-        reader::XmlEvent::Characters(s) | reader::XmlEvent::CData(s) => Ok(s),
-        unexpected => Err(unexpected_element_error(element_name, unexpected)),
+/// Whether `name` is the child element `local_name` of the element `parent`
+///
+/// Both the namespace and the local name must match, so that elements from other namespaces (e.g.
+/// extensions) are not mistaken for known elements. Child elements without a namespace are
+/// matched as if they were in the namespace of their parent, so documents whose local elements
+/// are unqualified can be read too. Types that require unqualified children, declared with
+/// `#[ex_em_ell(element_form_default = "unqualified")]`, use [`is_unqualified_child_element`]
+/// instead.
+pub fn is_child_element(name: &XmlName, parent: &XmlName, local_name: &str) -> bool {
+    let namespace = name.namespace.as_ref().or(parent.namespace.as_ref());
+    name.local_name == local_name && namespace == parent.namespace.as_ref()
+}
+
+/// Whether `name` is the child element `local_name` of an element whose children are
+/// unqualified, as they are under `elementFormDefault="unqualified"`, so that it has no namespace
+pub fn is_unqualified_child_element(name: &XmlName, _parent: &XmlName, local_name: &str) -> bool {
+    name.local_name == local_name && name.namespace.is_none()
+}
+
+/// Whether `attribute` is the attribute `local_name` without a namespace prefix
+pub fn is_unqualified_attribute<S>(attribute: &XmlAttribute<S>, local_name: &str) -> bool {
+    attribute.name.local_name == local_name && attribute.name.namespace.is_none()
+}
+
+/// Whether `attribute` is the attribute `local_name` in the [`XSI_NAMESPACE`], e.g. `xsi:type`
+pub fn is_xsi_attribute<S>(attribute: &XmlAttribute<S>, local_name: &str) -> bool {
+    attribute.name.local_name == local_name
+        && attribute.name.namespace.as_deref() == Some(XSI_NAMESPACE)
+}
+
+/// Read the `xsi:type` attribute of an element, resolving the prefix of the type name against
+/// the namespaces in scope at the element
+///
+/// A type name without a prefix is in the default namespace, if there is one.
+pub fn read_xsi_type<R: XmlEventSource, S: AsRef<str>>(
+    event_reader: &XmlEventReader<R>,
+    element_attributes: &[XmlAttribute<S>],
+    element_namespace: &Namespace,
+    element: &XmlName,
+) -> Result<XmlName, XmlReadError> {
+    let attribute = element_attributes
+        .iter()
+        .find(|attribute| is_xsi_attribute(attribute, "type"))
+        .ok_or_else(|| XmlReadError::RequiredDataMissing {
+            required_field: "xsi:type".to_string(),
+            element: element.to_string(),
+            position: Some(event_reader.position()),
+            path: Some(event_reader.path().clone()),
+        })?;
+
+    let value = attribute.value.as_ref().trim();
+    let (prefix, local_name) = match value.split_once(':') {
+        Some((prefix, local_name)) => (Some(prefix), local_name),
+        None => (None, value),
+    };
+    let namespace = element_namespace
+        .get(prefix.unwrap_or_default())
+        .filter(|uri| !uri.is_empty());
+    if prefix.is_some() && namespace.is_none() {
+        return Err(XmlReadError::InvalidParseError {
+            value: value.to_string(),
+            data_type: "xs:QName".to_string(),
+            element: format!("{}/@xsi:type", element),
+            position: Some(event_reader.position()),
+            path: Some(event_reader.path().clone()),
+        });
     }
+
+    Ok(XmlName {
+        local_name: local_name.to_string(),
+        namespace: namespace.map(str::to_string),
+        prefix: prefix.map(str::to_string),
+    })
 }
 
-pub fn closing_tag_or_error(
-    element: &OwnedName,
-) -> impl FnOnce(xml::reader::XmlEvent) -> Result<(), XmlReadError> {
-    let element = element.clone();
-    move |event| match event {
-        reader::XmlEvent::EndElement { name } if name == element => Ok(()),
-        unexpected => Err(unexpected_element_error(&element, unexpected)),
+/// Read the `xsi:nil` attribute of an element, which is `false` if it is not present
+pub fn read_xsi_nil<R: XmlEventSource, S: AsRef<str>>(
+    event_reader: &XmlEventReader<R>,
+    element_attributes: &[XmlAttribute<S>],
+    element: &XmlName,
+) -> Result<bool, XmlReadError> {
+    element_attributes
+        .iter()
+        .find(|attribute| is_xsi_attribute(attribute, "nil"))
+        .map(|attribute| {
+            parse_xml_text(
+                attribute.value.as_ref().trim(),
+                format!("{}/@xsi:nil", element),
+            )
+            .map_err(|error| {
+                error
+                    .with_position(event_reader.position())
+                    .with_path(event_reader.path())
+            })
+        })
+        .transpose()
+        .map(|nil| nil.unwrap_or(false))
+}
+
+/// Read the rest of an element that is `xsi:nil`, which may only contain comments and processing
+/// instructions
+pub fn read_nil_element<R: XmlEventSource>(
+    event_reader: &mut XmlEventReader<R>,
+    element: &XmlName,
+) -> Result<(), XmlReadError> {
+    match next_non_misc_event(event_reader)? {
+        XmlEvent::EndElement { name } if &name == element => Ok(()),
+        unexpected => Err(unexpected_event_error(event_reader, element, unexpected)),
     }
 }
 
-pub fn to_xml_write_error(
-    element: impl AsRef<str>,
-) -> impl FnOnce(xml::writer::Error) -> XmlWriteError {
+/// Write the element `tag` as `<tag xsi:nil="true"/>`, declaring the `xsi` namespace on it
+pub fn write_nil_element<W: XmlEventSink>(writer: &mut W, tag: &str) -> Result<(), XmlWriteError> {
+    write_event(
+        writer,
+        WriteEvent::start_element(tag)
+            .ns("xsi", XSI_NAMESPACE)
+            .attr("xsi:nil", "true"),
+        tag,
+    )?;
+    write_event(writer, WriteEvent::end_element(), tag)
+}
+
+/// Report that `xsi_type` is not one of the `expected` types, which are in the form
+/// `{namespace}name`
+pub fn unknown_xsi_type_error<R: XmlEventSource>(
+    event_reader: &XmlEventReader<R>,
+    element: impl ToString,
+    xsi_type: &XmlName,
+    expected: &[&str],
+) -> XmlReadError {
+    XmlReadError::UnknownXsiType {
+        xsi_type: xsi_type.prefixed(),
+        expected: expected.iter().map(ToString::to_string).collect(),
+        element: element.to_string(),
+        position: Some(event_reader.position()),
+        path: Some(event_reader.path().clone()),
+    }
+}
+
+/// Write `value` as the element `tag`, adding an `xsi:type` attribute for `type_name` to its
+/// start tag
+///
+/// If the type has a namespace, given as a prefix and namespace URI, it is declared on the
+/// element along with the `xsi` namespace.
+pub fn write_with_xsi_type<W: XmlEventSink, T: ToXmlElement + ?Sized>(
+    writer: &mut W,
+    value: &T,
+    tag: &str,
+    type_name: &str,
+    type_namespace: Option<(&str, &str)>,
+) -> Result<(), XmlWriteError> {
+    let xsi_type = match type_namespace {
+        Some((prefix, _)) => format!("{}:{}", prefix, type_name),
+        None => type_name.to_string(),
+    };
+    let mut sink = XsiTypeSink {
+        sink: writer,
+        xsi_type: Some((&xsi_type, type_namespace)),
+    };
+    value.to_xml_element(&mut sink, tag)
+}
+
+/// A sink that adds an `xsi:type` attribute, and the namespaces it needs, to the first start tag
+/// written to it
+///
+/// The sink it writes to is not part of its type, so that a value whose elements have an
+/// `xsi:type` of their own, e.g. a group of shapes that may contain groups, is written through
+/// the same type of sink at every level.
+struct XsiTypeSink<'s> {
+    sink: &'s mut dyn EventWriter,
+    xsi_type: Option<(&'s str, Option<(&'s str, &'s str)>)>,
+}
+
+/// The part of [`XmlEventSink`] that [`XsiTypeSink`] writes through
+trait EventWriter {
+    fn write_event(&mut self, event: WriteEvent<'_>) -> Result<(), XmlSinkError>;
+
+    fn xml_version(&self) -> XmlVersion;
+
+    fn encoding(&self) -> &str;
+}
+
+impl<W: XmlEventSink> EventWriter for W {
+    fn write_event(&mut self, event: WriteEvent<'_>) -> Result<(), XmlSinkError> {
+        XmlEventSink::write_event(self, event)
+    }
+
+    fn xml_version(&self) -> XmlVersion {
+        XmlEventSink::xml_version(self)
+    }
+
+    fn encoding(&self) -> &str {
+        XmlEventSink::encoding(self)
+    }
+}
+
+impl XmlEventSink for XsiTypeSink<'_> {
+    type Inner = ();
+
+    fn write_event(&mut self, event: WriteEvent<'_>) -> Result<(), XmlSinkError> {
+        match (event, self.xsi_type) {
+            (
+                WriteEvent::StartElement {
+                    name,
+                    mut attributes,
+                    mut namespaces,
+                },
+                Some((xsi_type, type_namespace)),
+            ) => {
+                self.xsi_type = None;
+                namespaces.push(("xsi", XSI_NAMESPACE));
+                namespaces.extend(type_namespace);
+                attributes.push(("xsi:type", xsi_type));
+                self.sink.write_event(WriteEvent::StartElement {
+                    name,
+                    attributes,
+                    namespaces,
+                })
+            }
+            (event, _) => self.sink.write_event(event),
+        }
+    }
+
+    fn xml_version(&self) -> XmlVersion {
+        self.sink.xml_version()
+    }
+
+    fn encoding(&self) -> &str {
+        self.sink.encoding()
+    }
+
+    fn into_inner(self) -> Self::Inner {}
+}
+
+/// Whether the event is markup that does not affect the structure of a document, i.e. a comment,
+/// a processing instruction or whitespace between elements
+pub fn is_misc_event<S>(event: &XmlEvent<S>) -> bool {
+    matches!(
+        event,
+        XmlEvent::Comment(_) | XmlEvent::ProcessingInstruction { .. } | XmlEvent::Whitespace(_)
+    )
+}
+
+/// Read the next event that is not a comment, processing instruction or whitespace
+pub fn next_non_misc_event<R: XmlEventSource>(
+    event_reader: &mut XmlEventReader<R>,
+) -> Result<XmlEvent, XmlReadError> {
+    loop {
+        let next_element = event_reader.next_event()?;
+        if !is_misc_event(&next_element) {
+            return Ok(next_element);
+        }
+    }
+}
+
+/// Read the next event like [`next_non_misc_event`], borrowing its text from the document
+pub fn next_non_misc_borrowed_event<'de, R: XmlBorrowedEventSource<'de>>(
+    event_reader: &mut XmlEventReader<R>,
+) -> Result<XmlEvent<Cow<'de, str>>, XmlReadError> {
+    loop {
+        let next_element = event_reader.next_borrowed_event()?;
+        if !is_misc_event(&next_element) {
+            return Ok(next_element);
+        }
+    }
+}
+
+pub fn to_xml_write_error(element: impl AsRef<str>) -> impl FnOnce(XmlSinkError) -> XmlWriteError {
     let element = element.as_ref().to_owned();
-    |error| XmlWriteError::XmlElementWriteError { error, element }
+    |error| match error {
+        XmlSinkError::Io(error) => XmlWriteError::IoError { error, element },
+        XmlSinkError::Invalid(error) => XmlWriteError::XmlElementWriteError { error, element },
+    }
 }
 
+/// Report an error from an [`XmlEventSource`] while reading `element_name`
+///
+/// The position of a limit that the source exceeded is filled in from the reader.
 pub fn to_xml_read_error(
     element_name: impl AsRef<str>,
-) -> impl FnOnce(xml::reader::Error) -> XmlReadError {
+) -> impl FnOnce(XmlSourceError) -> XmlReadError {
     let element_name = element_name.as_ref().to_owned();
-    |error| XmlReadError::ElementReadError {
-        error,
-        element: element_name,
+    |error| match error {
+        XmlSourceError::Io { error, position } => XmlReadError::IoError {
+            position: Some(position),
+            path: None,
+            error,
+            element: element_name,
+        },
+        XmlSourceError::Syntax { error, position } => XmlReadError::ElementReadError {
+            position: Some(position),
+            path: None,
+            error,
+            element: element_name,
+        },
+        XmlSourceError::LimitExceeded { limit } => XmlReadError::LimitExceeded {
+            limit,
+            element: element_name,
+            position: None,
+            path: None,
+        },
+    }
+}
+
+/// Write `event`, checking first that its names and text can be written as XML
+///
+/// Sinks escape markup characters, but may write names and characters that are not allowed in
+/// XML (e.g. `\u{0}`, or `--` in a comment) as they are, which would produce a document that
+/// cannot be read back.
+pub fn write_event<'a, W: XmlEventSink>(
+    writer: &mut W,
+    event: impl Into<WriteEvent<'a>>,
+    element: &str,
+) -> Result<(), XmlWriteError> {
+    let event = event.into();
+    let version = writer.xml_version();
+    let encoding = writer.encoding();
+    let check_name = |name: &str| {
+        check_xml_name(name, element)?;
+        check_encodable(name, encoding, element)
+    };
+    let check_text = |text: &str| {
+        check_xml_text(text, version, element)?;
+        check_encodable(text, encoding, element)
+    };
+    match &event {
+        WriteEvent::StartElement {
+            name,
+            attributes,
+            namespaces,
+        } => {
+            check_name(name)?;
+            for (name, value) in attributes {
+                check_name(name)?;
+                check_text(value)?;
+            }
+            for (prefix, uri) in namespaces {
+                if !prefix.is_empty() {
+                    check_name(prefix)?;
+                }
+                check_text(uri)?;
+            }
+        }
+        WriteEvent::Characters(text) | WriteEvent::CData(text) => check_text(text)?,
+        WriteEvent::Comment(comment) => {
+            check_text(comment)?;
+            if comment.contains("--") || comment.ends_with('-') {
+                return Err(XmlWriteError::InvalidCharacters {
+                    value: comment.to_string(),
+                    element: element.to_string(),
+                });
+            }
+        }
+        WriteEvent::ProcessingInstruction { name, data } => {
+            check_name(name)?;
+            if let Some(data) = data {
+                check_text(data)?;
+            }
+        }
+        WriteEvent::StartDocument { encoding, .. } => {
+            if !is_ascii_compatible(encoding) {
+                return Err(XmlWriteError::UnsupportedEncoding {
+                    encoding: encoding.to_string(),
+                });
+            }
+        }
+        WriteEvent::EndElement => {}
+    }
+
+    writer
+        .write_event(event)
+        .map_err(to_xml_write_error(element))
+}
+
+/// Check that `name` is an XML name, with an optional prefix, e.g. `ext:field`
+fn check_xml_name(name: &str, element: &str) -> Result<(), XmlWriteError> {
+    let is_ncname = |part: &str| {
+        let mut chars = part.chars();
+        chars
+            .next()
+            .is_some_and(|c| c != ':' && xml::common::is_name_start_char(c))
+            && chars.all(|c| c != ':' && xml::common::is_name_char(c))
+    };
+
+    let valid = match name.split_once(':') {
+        Some((prefix, local_name)) => is_ncname(prefix) && is_ncname(local_name),
+        None => is_ncname(name),
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(XmlWriteError::InvalidName {
+            name: name.to_string(),
+            element: element.to_string(),
+        })
     }
 }
 
-pub fn unexpected_element_error(
+/// Check that `text` only contains characters that can be written as they are in `version`
+///
+/// XML 1.1 allows control characters in documents, but only as character references, so they
+/// are rejected here as they are for XML 1.0.
+fn check_xml_text(text: &str, version: XmlVersion, element: &str) -> Result<(), XmlWriteError> {
+    let is_char = match version {
+        XmlVersion::Version10 => xml::common::is_xml10_char,
+        XmlVersion::Version11 => xml::common::is_xml11_char_not_restricted,
+    };
+    if text.chars().all(is_char) {
+        Ok(())
+    } else {
+        Err(XmlWriteError::InvalidCharacters {
+            value: text.to_string(),
+            element: element.to_string(),
+        })
+    }
+}
+
+/// Check that `text` is written the same in the declared `encoding` as it is in UTF-8, which it
+/// is always written in, i.e. that it is ASCII unless the encoding is UTF-8
+fn check_encodable(text: &str, encoding: &str, element: &str) -> Result<(), XmlWriteError> {
+    let is_utf8 = encoding.eq_ignore_ascii_case("utf-8") || encoding.eq_ignore_ascii_case("utf8");
+    if is_utf8 || text.is_ascii() {
+        Ok(())
+    } else {
+        Err(XmlWriteError::UnencodableCharacters {
+            value: text.to_string(),
+            encoding: encoding.to_string(),
+            element: element.to_string(),
+        })
+    }
+}
+
+/// Whether ASCII text is written the same in `encoding` as in UTF-8, which is not the case for
+/// the encodings that use two or four bytes for every character
+fn is_ascii_compatible(encoding: &str) -> bool {
+    let encoding = encoding.to_ascii_uppercase();
+    ![
+        "UTF-16",
+        "UTF16",
+        "UTF-32",
+        "UTF32",
+        "UCS-2",
+        "UCS-4",
+        "ISO-10646-UCS",
+    ]
+    .iter()
+    .any(|prefix| encoding.starts_with(prefix))
+}
+
+/// Report `unexpected`, which should be the last event read from `event_reader`
+pub fn unexpected_event_error<R: XmlEventSource, S>(
+    event_reader: &XmlEventReader<R>,
     element: impl ToString,
-    unexpected: xml::reader::XmlEvent,
+    unexpected: XmlEvent<S>,
 ) -> XmlReadError {
-    XmlReadError::UnexpectedElementReadError {
-        error: format!("Got unexpected element {:?}", unexpected),
-        element: element.to_string(),
+    unexpected_event_with_known_values_error(event_reader, element, Vec::new(), unexpected)
+}
+
+/// Report `unexpected`, which should be the last event read from `event_reader`, and skip it if
+/// errors are being collected
+pub fn skip_unexpected_event<R: XmlEventSource, S>(
+    event_reader: &mut XmlEventReader<R>,
+    element: impl ToString,
+    valid_elements: &[&str],
+    unexpected: XmlEvent<S>,
+) -> Result<(), XmlReadError> {
+    let depth = unexpected_event_depth(event_reader, &unexpected);
+    let error = unexpected_event_with_known_values_error(
+        event_reader,
+        element,
+        valid_elements.iter().map(ToString::to_string).collect(),
+        unexpected,
+    );
+    event_reader
+        .recover_element(Err(error), depth)
+        .map(|_: Option<()>| ())
+}
+
+/// The depth to skip to after an unexpected event, which is the whole subtree for a start tag
+/// and nothing otherwise
+pub(crate) fn unexpected_event_depth<R: XmlEventSource, S>(
+    event_reader: &XmlEventReader<R>,
+    unexpected: &XmlEvent<S>,
+) -> usize {
+    match unexpected {
+        XmlEvent::StartElement { .. } => event_reader.depth(),
+        _ => event_reader.depth() + 1,
     }
 }
 
-pub fn unexpected_element_with_known_values_error(
+/// Report `unexpected`, which should be the last event read from `event_reader`, where one of
+/// `valid_elements` was expected
+pub fn unexpected_event_with_known_values_error<R: XmlEventSource, S>(
+    event_reader: &XmlEventReader<R>,
+    element: impl ToString,
+    valid_elements: Vec<String>,
+    unexpected: XmlEvent<S>,
+) -> XmlReadError {
+    unexpected_event(element, valid_elements, unexpected)
+        .with_position(event_reader.position())
+        .with_path(event_reader.path())
+}
+
+fn unexpected_event<S>(
     element: impl ToString,
     valid_elements: Vec<String>,
-    unexpected: xml::reader::XmlEvent,
+    unexpected: XmlEvent<S>,
 ) -> XmlReadError {
+    let found_name = match &unexpected {
+        XmlEvent::StartElement { name, .. } | XmlEvent::EndElement { name } => {
+            Some(name.prefixed().into())
+        }
+        XmlEvent::ProcessingInstruction { name, .. } => Some(name.as_str().into()),
+        _ => None,
+    };
     XmlReadError::UnexpectedElementReadError {
-        error: format!(
-            "Got unexpected element {:?}, expected one of: {}",
-            unexpected,
-            valid_elements.iter().join(", ")
-        ),
+        found: XmlEventKind::from(&unexpected),
+        found_name,
+        expected: valid_elements.into_boxed_slice(),
         element: element.to_string(),
+        position: None,
+        path: None,
+    }
+}
+
+#[deprecated(note = "use `unexpected_event_error`, which records where the event was found")]
+pub fn unexpected_element_error<S>(
+    element: impl ToString,
+    unexpected: XmlEvent<S>,
+) -> XmlReadError {
+    unexpected_event(element, Vec::new(), unexpected)
+}
+
+#[deprecated(
+    note = "use `unexpected_event_with_known_values_error`, which records where the event was found"
+)]
+pub fn unexpected_element_with_known_values_error<S>(
+    element: impl ToString,
+    valid_elements: Vec<String>,
+    unexpected: XmlEvent<S>,
+) -> XmlReadError {
+    unexpected_event(element, valid_elements, unexpected)
+}
+
+#[deprecated(note = "use `read_simple_tag`, which also reads text split around comments")]
+pub fn inner_text_or_error(
+    element_name: impl AsRef<str>,
+) -> impl FnOnce(XmlEvent) -> Result<String, XmlReadError> {
+    let element_name = element_name.as_ref().to_owned();
+    |event| match event {
+        XmlEvent::Characters(s) | XmlEvent::CData(s) => Ok(s),
+        unexpected => Err(unexpected_event(element_name, Vec::new(), unexpected)),
+    }
+}
+
+#[deprecated(note = "use `unexpected_event_error` for any event other than the closing tag")]
+pub fn closing_tag_or_error(
+    element: &XmlName,
+) -> impl FnOnce(XmlEvent) -> Result<(), XmlReadError> {
+    let element = element.clone();
+    move |event| match event {
+        XmlEvent::EndElement { name } if name == element => Ok(()),
+        unexpected => Err(unexpected_event(&element, Vec::new(), unexpected)),
     }
 }
@@ -0,0 +1,193 @@
+#![cfg(feature = "tokio")]
+
+use std::time::Duration;
+
+use ex_em_ell::ReadOptions;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+#[derive(Debug, PartialEq, ex_em_ell::FromXmlDocument, ex_em_ell::ToXmlDocument)]
+struct Catalog {
+    #[ex_em_ell(attribute)]
+    version: u32,
+
+    #[ex_em_ell(comments)]
+    comments: Vec<String>,
+
+    title: String,
+
+    items: Vec<Item>,
+}
+
+#[derive(
+    Debug, PartialEq, ex_em_ell::FromXmlElement, ex_em_ell::ToXmlElement, ex_em_ell::NamedXmlElement,
+)]
+struct Item {
+    #[ex_em_ell(attribute)]
+    id: String,
+
+    name: String,
+
+    price: f64,
+}
+
+const CATALOG: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<catalog version="2">
+  <!-- Spring catalog -->
+  <title>Tools &amp; <![CDATA[<Hardware>]]></title>
+  <items>
+    <item id="a-1">
+      <name>Hammer</name>
+      <price>12.5</price>
+    </item>
+    <item id="b&#45;2">
+      <name>Wrench</name>
+      <price>8</price>
+    </item>
+  </items>
+</catalog>"#;
+
+/// Write `document` to one end of a duplex stream, a few bytes at a time, while reading it from
+/// the other end
+async fn read_in_chunks(document: &str) -> Result<Catalog, ex_em_ell::errors::XmlReadError> {
+    let (mut client, server) = tokio::io::duplex(8);
+    let write = async move {
+        for chunk in document.as_bytes().chunks(5) {
+            // The document stops being read once the value has failed to be read from it
+            if client.write_all(chunk).await.is_err() {
+                break;
+            }
+        }
+    };
+
+    let (_, catalog) = tokio::join!(write, ex_em_ell::async_io::from_reader(server));
+    catalog
+}
+
+#[tokio::test]
+async fn test_same_value_as_from_reader() {
+    let expected: Catalog =
+        ex_em_ell::from_reader(CATALOG.as_bytes()).expect("Should read the catalog");
+
+    let actual = read_in_chunks(CATALOG)
+        .await
+        .expect("Should read the catalog in chunks");
+
+    assert_eq!(expected, actual);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_same_output_as_to_writer() {
+    let catalog: Catalog =
+        ex_em_ell::from_reader(CATALOG.as_bytes()).expect("Should read the catalog");
+    let expected = ex_em_ell::to_string_pretty(&catalog).expect("Should write the catalog");
+
+    // The document is written while this task is blocked, so it is read by another
+    let (client, mut server) = tokio::io::duplex(8);
+    let read = tokio::spawn(async move {
+        let mut actual = String::new();
+        server.read_to_string(&mut actual).await.map(|_| actual)
+    });
+    ex_em_ell::async_io::to_writer_pretty(client, &catalog)
+        .await
+        .expect("Should write the catalog");
+    let actual = read
+        .await
+        .expect("Should finish reading")
+        .expect("Should read the written catalog");
+
+    assert_eq!(expected, actual);
+}
+
+#[tokio::test]
+async fn test_same_output_on_current_thread_runtime() {
+    let catalog: Catalog =
+        ex_em_ell::from_reader(CATALOG.as_bytes()).expect("Should read the catalog");
+    let expected = ex_em_ell::to_string_pretty(&catalog).expect("Should write the catalog");
+
+    let (client, mut server) = tokio::io::duplex(1024);
+    let mut actual = String::new();
+    let (written, read) = tokio::join!(
+        ex_em_ell::async_io::to_writer_pretty(client, &catalog),
+        server.read_to_string(&mut actual),
+    );
+    written.expect("Should write the catalog");
+    read.expect("Should read the written catalog");
+
+    assert_eq!(expected, actual);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_round_trip() {
+    let catalog: Catalog =
+        ex_em_ell::from_reader(CATALOG.as_bytes()).expect("Should read the catalog");
+
+    let (client, server) = tokio::io::duplex(8);
+    let read = tokio::spawn(ex_em_ell::async_io::from_reader::<Catalog, _>(server));
+    ex_em_ell::async_io::to_writer(client, &catalog)
+        .await
+        .expect("Should write the catalog");
+
+    let read = read.await.expect("Should finish reading");
+    assert_eq!(catalog, read.expect("Should read the written catalog"));
+}
+
+#[tokio::test]
+async fn test_error_position() {
+    let document = CATALOG.replace("<price>8</price>", "<price>eight</price>");
+    let error = read_in_chunks(&document)
+        .await
+        .expect_err("Should fail to parse the price");
+
+    insta::assert_snapshot!(error.to_string(), @"Could not parse eight as xs:double on price at /catalog/items/item[2]/price (12:7)");
+}
+
+#[tokio::test]
+async fn test_syntax_error_after_invalid_value() {
+    let document = CATALOG
+        .replace("<price>8</price>", "<price>eight</price>")
+        .replace("</catalog>", "</catalogue>");
+    let error = read_in_chunks(&document)
+        .await
+        .expect_err("Should fail to read the document");
+
+    // The invalid value is reported first, as it is when reading synchronously
+    insta::assert_snapshot!(error.to_string(), @"Could not parse eight as xs:double on price at /catalog/items/item[2]/price (12:7)");
+}
+
+#[tokio::test]
+async fn test_limit_exceeded_before_document_ends() {
+    let (mut client, server) = tokio::io::duplex(1024);
+    let start = CATALOG
+        .split("<items>")
+        .next()
+        .expect("Should have a start");
+    client
+        .write_all(start.as_bytes())
+        .await
+        .expect("Should write the start of the catalog");
+
+    // The rest of the document never arrives, so the limit has to be checked as events are read
+    let options = ReadOptions::default().max_elements(1);
+    let read = ex_em_ell::async_io::from_reader_with_options::<Catalog, _>(server, options);
+    let error = tokio::time::timeout(Duration::from_secs(10), read)
+        .await
+        .expect("Should not wait for the rest of the document")
+        .expect_err("Should exceed the element limit");
+
+    insta::assert_snapshot!(error.to_string(), @"Exceeded the limit of 1 elements while reading catalog at /catalog (4:3)");
+    drop(client);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_write_error_stops_serializing() {
+    let catalog: Catalog =
+        ex_em_ell::from_reader(CATALOG.as_bytes()).expect("Should read the catalog");
+
+    let (client, server) = tokio::io::duplex(8);
+    drop(server);
+    let error = ex_em_ell::async_io::to_writer(client, &catalog)
+        .await
+        .expect_err("Should fail to write to a closed stream");
+
+    insta::assert_snapshot!(error.to_string(), @"Failed to write XML for document: broken pipe");
+}
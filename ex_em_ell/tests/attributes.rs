@@ -0,0 +1,150 @@
+use ex_em_ell::XmlSimpleType;
+
+#[derive(Debug, PartialEq, ex_em_ell::FromXmlDocument, ex_em_ell::ToXmlDocument)]
+struct Example {
+    #[ex_em_ell(attribute)]
+    version: u32,
+
+    #[ex_em_ell(attribute)]
+    enabled: bool,
+
+    count: u64,
+
+    child: ExampleChild,
+}
+
+#[derive(Debug, PartialEq, ex_em_ell::FromXmlElement, ex_em_ell::ToXmlElement)]
+struct ExampleChild {
+    #[ex_em_ell(attribute)]
+    kind: String,
+
+    identifier: Identifier,
+}
+
+#[derive(Debug, PartialEq)]
+struct Identifier(String);
+
+impl XmlSimpleType for Identifier {
+    fn xml_type_name() -> &'static str {
+        "identifier"
+    }
+
+    fn to_xml_text(&self) -> String {
+        self.0.clone()
+    }
+
+    fn from_xml_text(text: &str) -> Option<Self> {
+        text.contains('-').then(|| Self(text.to_string()))
+    }
+}
+
+#[test]
+fn test_example_xmls() {
+    insta::glob!("data/attributes/valid_*.xml", |path| {
+        let file =
+            std::fs::File::open(path).unwrap_or_else(|_| panic!("Failed to read file: {path:?}"));
+        let example: Example = ex_em_ell::from_reader(&file)
+            .unwrap_or_else(|_| panic!("Failed to parse the XML file: {path:?}"));
+
+        let round_trip = ex_em_ell::to_string_pretty(&example).expect("Failed to output XML");
+        insta::assert_snapshot!(round_trip);
+    });
+}
+
+#[test]
+fn test_invalid_attribute_value() {
+    let xml = r#"<example version="two" enabled="true"><count>3</count><child kind="leaf"><identifier>A-1</identifier></child></example>"#;
+    let error = ex_em_ell::from_reader::<Example, _>(xml.as_bytes())
+        .expect_err("Should fail to parse the version attribute");
+
+    insta::assert_snapshot!(error.to_string(), @"Could not parse two as xs:unsignedInt on example/@version at /example (1:38)");
+}
+
+#[test]
+fn test_missing_attribute() {
+    let xml = r#"<example version="2" enabled="true"><count>3</count><child><identifier>A-1</identifier></child></example>"#;
+    let error = ex_em_ell::from_reader::<Example, _>(xml.as_bytes())
+        .expect_err("Should fail without the kind attribute");
+
+    insta::assert_snapshot!(error.to_string(), @"Ended element child at /example/child (1:53) without data for required field kind");
+}
+
+#[test]
+fn test_invalid_simple_type_value() {
+    let xml = r#"<example version="2" enabled="true"><count>3</count><child kind="leaf"><identifier>A1</identifier></child></example>"#;
+    let error = ex_em_ell::from_reader::<Example, _>(xml.as_bytes())
+        .expect_err("Should fail to parse the identifier");
+
+    insta::assert_snapshot!(error.to_string(), @"Could not parse A1 as identifier on identifier at /example/child/identifier (1:72)");
+}
+
+#[derive(Debug, PartialEq, ex_em_ell::FromXmlDocument, ex_em_ell::ToXmlDocument)]
+struct OptionalAttributes {
+    #[ex_em_ell(attribute)]
+    version: Option<u32>,
+
+    #[ex_em_ell(attribute, list)]
+    tags: Option<Vec<String>>,
+
+    name: String,
+}
+
+#[test]
+fn test_optional_attributes() {
+    let present: OptionalAttributes = ex_em_ell::from_str(
+        r#"<optionalAttributes version="2" tags="a b"><name>x</name></optionalAttributes>"#,
+    )
+    .expect("Should read the attributes");
+    assert_eq!(present.version, Some(2));
+    assert_eq!(present.tags, Some(vec!["a".to_string(), "b".to_string()]));
+
+    let absent: OptionalAttributes =
+        ex_em_ell::from_str(r#"<optionalAttributes><name>x</name></optionalAttributes>"#)
+            .expect("Should read without the attributes");
+    assert_eq!(absent.version, None);
+    assert_eq!(absent.tags, None);
+
+    insta::assert_snapshot!(ex_em_ell::to_string(&present).expect("Should write the attributes"), @r###"<?xml version="1.0" encoding="utf-8"?><optionalAttributes version="2" tags="a b"><name>x</name></optionalAttributes>"###);
+    insta::assert_snapshot!(ex_em_ell::to_string(&absent).expect("Should write without the attributes"), @r###"<?xml version="1.0" encoding="utf-8"?><optionalAttributes><name>x</name></optionalAttributes>"###);
+}
+
+#[test]
+fn test_invalid_optional_attribute() {
+    let error = ex_em_ell::from_str::<OptionalAttributes>(
+        r#"<optionalAttributes version="two"><name>x</name></optionalAttributes>"#,
+    )
+    .expect_err("Should fail to parse the version attribute");
+
+    insta::assert_snapshot!(error.to_string(), @"Could not parse two as xs:unsignedInt on optionalAttributes/@version at /optionalAttributes (1:34)");
+}
+
+/// A newtype that implements [`XmlSimpleType`] once can be used as an attribute, an element and
+/// the items of a list
+#[derive(Debug, PartialEq, ex_em_ell::FromXmlDocument, ex_em_ell::ToXmlDocument)]
+struct Reference {
+    #[ex_em_ell(attribute)]
+    target: Identifier,
+
+    source: Identifier,
+
+    #[ex_em_ell(list)]
+    aliases: Vec<Identifier>,
+}
+
+#[test]
+fn test_newtype() {
+    let reference = Reference {
+        target: Identifier("lib-1".to_string()),
+        source: Identifier("app-2".to_string()),
+        aliases: vec![Identifier("a-1".to_string()), Identifier("b-2".to_string())],
+    };
+    let xml = ex_em_ell::to_string(&reference).expect("Should write the reference");
+    insta::assert_snapshot!(xml, @r###"<?xml version="1.0" encoding="utf-8"?><reference target="lib-1"><source>app-2</source><aliases>a-1 b-2</aliases></reference>"###);
+
+    let read: Reference = ex_em_ell::from_str(&xml).expect("Should read the reference");
+    assert_eq!(reference, read);
+
+    let error = ex_em_ell::from_str::<Reference>(&xml.replace("b-2", "b2"))
+        .expect_err("Should fail to parse the alias");
+    insta::assert_snapshot!(error.to_string(), @"Could not parse b2 as identifier on item 2 of aliases at /reference/aliases (1:87)");
+}
@@ -0,0 +1,166 @@
+use std::collections::VecDeque;
+
+use ex_em_ell::backend::{XmlEventSink, XmlEventSource, XmlSinkError, XmlSourceError};
+use ex_em_ell::errors::TextPosition;
+use ex_em_ell::events::{Namespace, WriteEvent, XmlAttribute, XmlEvent, XmlName};
+use ex_em_ell::{ReadOptions, WriteOptions, XmlEventReader, XmlVersion};
+
+#[derive(Debug, PartialEq, ex_em_ell::FromXmlDocument, ex_em_ell::ToXmlDocument)]
+struct Example {
+    #[ex_em_ell(attribute)]
+    version: u32,
+    field: String,
+}
+
+/// A source that replays a list of events, one per line
+struct ReplaySource {
+    events: VecDeque<XmlEvent>,
+    line: u64,
+}
+
+impl ReplaySource {
+    fn new(events: impl IntoIterator<Item = XmlEvent>) -> Self {
+        Self {
+            events: events.into_iter().collect(),
+            line: 0,
+        }
+    }
+}
+
+impl XmlEventSource for ReplaySource {
+    fn next_event(&mut self) -> Result<XmlEvent, XmlSourceError> {
+        self.line += 1;
+        self.events
+            .pop_front()
+            .ok_or_else(|| XmlSourceError::Syntax {
+                error: "Unexpected end of the events".into(),
+                position: self.position(),
+            })
+    }
+
+    fn position(&self) -> TextPosition {
+        TextPosition {
+            line: self.line.max(1),
+            column: 1,
+        }
+    }
+
+    fn doctype(&self) -> Option<&str> {
+        None
+    }
+}
+
+/// A sink that records a line describing each event
+#[derive(Default)]
+struct RecordingSink {
+    lines: Vec<String>,
+}
+
+impl XmlEventSink for RecordingSink {
+    type Inner = Vec<String>;
+
+    fn write_event(&mut self, event: WriteEvent<'_>) -> Result<(), XmlSinkError> {
+        self.lines.push(format!("{event:?}"));
+        Ok(())
+    }
+
+    fn into_inner(self) -> Vec<String> {
+        self.lines
+    }
+}
+
+fn example_events(field: &str) -> Vec<XmlEvent> {
+    vec![
+        XmlEvent::StartDocument {
+            version: XmlVersion::Version10,
+            encoding: "utf-8".to_string(),
+            standalone: None,
+        },
+        XmlEvent::StartElement {
+            name: XmlName::local("example"),
+            attributes: vec![XmlAttribute {
+                name: XmlName::local("version"),
+                value: "1".to_string(),
+            }],
+            namespace: Namespace::default(),
+        },
+        XmlEvent::StartElement {
+            name: XmlName::local("field"),
+            attributes: Vec::new(),
+            namespace: Namespace::default(),
+        },
+        XmlEvent::Characters(field.to_string()),
+        XmlEvent::EndElement {
+            name: XmlName::local("field"),
+        },
+        XmlEvent::EndElement {
+            name: XmlName::local("example"),
+        },
+        XmlEvent::EndDocument,
+    ]
+}
+
+#[test]
+fn test_read_from_source() {
+    let source = ReplaySource::new(example_events("value"));
+    let example: Example =
+        ex_em_ell::from_source(source, ReadOptions::default()).expect("Should read the example");
+
+    assert_eq!(
+        example,
+        Example {
+            version: 1,
+            field: "value".to_string(),
+        }
+    );
+}
+
+#[test]
+fn test_source_error() {
+    let mut events = example_events("value");
+    events.truncate(4);
+    let source = ReplaySource::new(events);
+    let error = ex_em_ell::from_source::<Example, _>(source, ReadOptions::default())
+        .expect_err("Should fail at the end of the events");
+
+    assert!(!error.is_recoverable());
+    insta::assert_snapshot!(error.to_string(), @"Failed to deserialize XML while reading field at /example/field (5:1): Unexpected end of the events");
+}
+
+#[test]
+fn test_write_to_sink() {
+    let example = Example {
+        version: 1,
+        field: "value".to_string(),
+    };
+    let mut sink = RecordingSink::default();
+    ex_em_ell::to_sink(&mut sink, &example, &WriteOptions::default())
+        .expect("Should write the example");
+
+    insta::assert_snapshot!(sink.into_inner().join("\n"), @r###"
+    StartDocument { version: Version10, encoding: "utf-8", standalone: None }
+    StartElement { name: "example", attributes: [("version", "1")], namespaces: [] }
+    StartElement { name: "field", attributes: [], namespaces: [] }
+    Characters("value")
+    EndElement
+    EndElement
+    "###);
+}
+
+#[test]
+fn test_peek_event() {
+    let mut reader = XmlEventReader::from_source(
+        ReplaySource::new(example_events("value")),
+        ReadOptions::default(),
+    );
+    reader.next_event().expect("Should read the declaration");
+
+    let peeked = reader.peek_event().expect("Should peek at the root");
+    assert!(matches!(&peeked, XmlEvent::StartElement { name, .. } if name.local_name == "example"));
+    assert_eq!(reader.depth(), 0);
+
+    let event = reader.next_event().expect("Should read the root");
+    assert_eq!(event, peeked);
+    assert_eq!(reader.depth(), 1);
+    assert_eq!(reader.path().to_string(), "/example");
+}
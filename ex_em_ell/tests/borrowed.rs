@@ -0,0 +1,107 @@
+#![cfg(feature = "quick-xml")]
+
+use std::borrow::Cow;
+
+#[derive(Debug, PartialEq, ex_em_ell::FromXmlDocumentBorrowed)]
+struct Library<'a> {
+    #[ex_em_ell(attribute)]
+    name: &'a str,
+
+    #[ex_em_ell(comments)]
+    comments: Vec<Cow<'a, str>>,
+
+    books: Vec<Book<'a>>,
+}
+
+#[derive(Debug, PartialEq, ex_em_ell::FromXmlElementBorrowed, ex_em_ell::NamedXmlElement)]
+struct Book<'de> {
+    #[ex_em_ell(attribute)]
+    isbn: Cow<'de, str>,
+
+    title: &'de str,
+
+    summary: Cow<'de, str>,
+
+    pages: u32,
+}
+
+/// Whether `text` is a slice of `input`, rather than a copy of it
+fn is_within(input: &str, text: &str) -> bool {
+    let range = input.as_bytes().as_ptr_range();
+    range.contains(&text.as_ptr()) && text.len() <= input.len()
+}
+
+#[test]
+fn test_borrows_text_from_the_input() {
+    let xml = r#"<?xml version="1.0" encoding="utf-8"?>
+<library name="Central">
+  <!-- Opening hours -->
+  <books>
+    <book isbn="0-19-852663-6">
+      <title>Dune</title>
+      <summary>Spice &amp; sand</summary>
+      <pages>412</pages>
+    </book>
+    <book isbn="0&#45;00-000000-0">
+      <title>Emma</title>
+      <summary>A matchmaker</summary>
+      <pages>474</pages>
+    </book>
+  </books>
+</library>"#;
+
+    let library: Library = ex_em_ell::from_str_borrowed(xml).expect("Should read the library");
+
+    assert_eq!(library.name, "Central");
+    assert!(is_within(xml, library.name));
+    assert!(matches!(
+        &library.comments[..],
+        [Cow::Borrowed(" Opening hours ")]
+    ));
+
+    let [dune, emma] = &library.books[..] else {
+        panic!("Should read two books: {:?}", library.books);
+    };
+    assert_eq!(dune.title, "Dune");
+    assert!(is_within(xml, dune.title));
+    assert!(matches!(dune.isbn, Cow::Borrowed("0-19-852663-6")));
+    assert_eq!(dune.pages, 412);
+
+    // Text that had to be decoded is the only text that is copied
+    assert!(matches!(&dune.summary, Cow::Owned(summary) if summary == "Spice & sand"));
+    assert!(matches!(&emma.isbn, Cow::Owned(isbn) if isbn == "0-00-000000-0"));
+    assert!(matches!(emma.summary, Cow::Borrowed("A matchmaker")));
+}
+
+#[test]
+fn test_reads_bytes() {
+    let xml = br#"<library name="Central"><books/></library>"#;
+
+    let library: Library = ex_em_ell::from_slice_borrowed(xml).expect("Should read the library");
+
+    assert_eq!(
+        library,
+        Library {
+            name: "Central",
+            comments: Vec::new(),
+            books: Vec::new(),
+        }
+    );
+}
+
+#[test]
+fn test_borrowed_str_needs_text_as_written() {
+    let xml = r#"<library name="Central"><books><book isbn="0-19-852663-6"><title>Dune &amp; Emma</title><summary>Two</summary><pages>886</pages></book></books></library>"#;
+    let error = ex_em_ell::from_str_borrowed::<Library>(xml)
+        .expect_err("Should not borrow text that had to be decoded");
+
+    insta::assert_snapshot!(error.to_string(), @r###"Invalid title at /library/books/book/title (1:59): "Dune & Emma" had to be decoded, so it cannot be borrowed as xs:string"###);
+}
+
+#[test]
+fn test_invalid_utf8() {
+    let error = ex_em_ell::from_slice_borrowed::<Library>(b"<library name=\"\xFF\"/>")
+        .expect_err("Should not read bytes that are not UTF-8");
+
+    insta::assert_snapshot!(error.to_string(), @"Failed to deserialize XML while reading document: invalid utf-8 sequence of 1 bytes from index 15");
+}
@@ -0,0 +1,68 @@
+#[derive(Debug, PartialEq, ex_em_ell::FromXmlDocument)]
+struct Example {
+    #[ex_em_ell(attribute)]
+    version: u32,
+    children: Vec<ExampleChild>,
+    name: String,
+}
+
+#[derive(Debug, PartialEq, ex_em_ell::FromXmlElement, ex_em_ell::NamedXmlElement)]
+#[ex_em_ell(name = "child")]
+struct ExampleChild {
+    field: String,
+    count: u32,
+}
+
+fn collect_errors(xml: &str) -> String {
+    ex_em_ell::from_reader_collect_errors::<Example, _>(xml.as_bytes())
+        .expect_err("Should fail to read the document")
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[test]
+fn test_valid_document_is_read() {
+    let xml = r#"<example version="1"><children>
+        <child><field>value</field><count>1</count></child>
+    </children><name>example</name></example>"#;
+    let collected: Example = ex_em_ell::from_reader_collect_errors(xml.as_bytes())
+        .expect("Should read a valid document");
+
+    assert_eq!(
+        collected,
+        ex_em_ell::from_str(xml).expect("Should read a valid document")
+    );
+}
+
+#[test]
+fn test_all_errors_are_reported() {
+    let xml = r#"<example version="one"><children>
+        <child><field>value</field><count>two</count></child>
+        <child><field>value</field></child>
+        <child><field>value</field><count>3</count><extra><nested /></extra></child>
+    </children></example>"#;
+
+    insta::assert_snapshot!(collect_errors(xml), @r###"
+    Could not parse one as xs:unsignedInt on example/@version at /example (1:23)
+    Could not parse two as xs:unsignedInt on count at /example/children/child/count (2:36)
+    Ended element child at /example/children/child[2] (3:9) without data for required field count
+    Found `<extra>` while reading child, expected one of `<field>`, `<count>` at /example/children/child[3]/extra (4:52)
+    Ended element example at /example (1:23) without data for required field name
+    "###);
+}
+
+#[test]
+fn test_syntax_error_stops_reading() {
+    let xml = r#"<example version="one"><children>
+        <child><field>value</fild></child>
+    </children></example>"#;
+
+    let errors = ex_em_ell::from_reader_collect_errors::<Example, _>(xml.as_bytes())
+        .expect_err("Should fail to read the document");
+
+    assert_eq!(errors.len(), 2);
+    assert!(errors[0].is_recoverable());
+    assert!(!errors[1].is_recoverable());
+}
@@ -0,0 +1,35 @@
+/// Define a test that reads the documents matching `$glob` as `$type` with the quick-xml backend,
+/// and writes them back with it
+///
+/// The output should match the xml-rs backend, so the snapshots of the test that reads them with
+/// xml-rs are shared.
+macro_rules! test_example_xmls_quick_xml {
+    ($type:ty, $glob:literal) => {
+        #[test]
+        #[cfg(feature = "quick-xml")]
+        fn test_example_xmls_quick_xml() {
+            use ex_em_ell::backend::{
+                quick_xml::{QuickXmlSink, QuickXmlSource},
+                XmlEventSink,
+            };
+
+            insta::glob!($glob, |path| {
+                let file = std::fs::File::open(path)
+                    .unwrap_or_else(|_| panic!("Failed to read file: {path:?}"));
+                let options = ex_em_ell::ReadOptions::default();
+                let source = QuickXmlSource::new(std::io::BufReader::new(file), &options);
+                let example: $type = ex_em_ell::from_source(source, options)
+                    .unwrap_or_else(|_| panic!("Failed to parse the XML file: {path:?}"));
+
+                let options = ex_em_ell::WriteOptions::default().perform_indent(true);
+                let mut sink = QuickXmlSink::new(Vec::new(), &options);
+                ex_em_ell::to_sink(&mut sink, &example, &options).expect("Failed to output XML");
+                let round_trip =
+                    String::from_utf8(sink.into_inner()).expect("Output should be UTF-8");
+                insta::assert_snapshot!("example_xmls", round_trip);
+            });
+        }
+    };
+}
+
+pub(crate) use test_example_xmls_quick_xml;
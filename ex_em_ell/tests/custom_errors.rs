@@ -0,0 +1,179 @@
+use std::io::{Read, Write};
+
+use ex_em_ell::backend::{xml_rs::XmlRsSink, XmlEventSink, XmlEventSource};
+use ex_em_ell::errors::{XmlReadError, XmlWriteError};
+use ex_em_ell::events::{Namespace, XmlAttribute, XmlName};
+use ex_em_ell::{
+    xml_utils, FromXmlElement, ToXmlElement, WriteOptions, XmlEventReader, XmlVersion,
+};
+
+#[derive(Debug, PartialEq, ex_em_ell::FromXmlDocument, ex_em_ell::ToXmlDocument)]
+struct Example {
+    hash: Hash,
+}
+
+/// A SHA-1 hash, which must be 40 hexadecimal digits
+#[derive(Debug, PartialEq)]
+struct Hash(String);
+
+#[derive(Debug, thiserror::Error)]
+#[error("{0:?} is not a SHA-1 hash")]
+struct InvalidHash(String);
+
+impl FromXmlElement for Hash {
+    fn from_xml_element<R: XmlEventSource>(
+        reader: &mut XmlEventReader<R>,
+        element_name: &XmlName,
+        _element_attributes: &[XmlAttribute],
+        _element_namespace: &Namespace,
+    ) -> Result<Self, XmlReadError> {
+        let text = xml_utils::read_simple_tag(reader, element_name)?;
+        if text.len() == 40 && text.chars().all(|c| c.is_ascii_hexdigit()) {
+            Ok(Self(text))
+        } else {
+            Err(XmlReadError::custom(element_name, InvalidHash(text)))
+        }
+    }
+}
+
+impl ToXmlElement for Hash {
+    fn to_xml_element<W: XmlEventSink>(
+        &self,
+        writer: &mut W,
+        tag: &str,
+    ) -> Result<(), XmlWriteError> {
+        if self.0.len() != 40 {
+            return Err(XmlWriteError::custom(tag, InvalidHash(self.0.clone())));
+        }
+        xml_utils::write_simple_tag(writer, tag, &self.0)
+    }
+}
+
+#[derive(Debug, PartialEq, ex_em_ell::ToXmlDocument)]
+struct Commented {
+    #[ex_em_ell(comments)]
+    comments: Vec<String>,
+    field: String,
+}
+
+struct FailingIo;
+
+impl Read for FailingIo {
+    fn read(&mut self, _buf: &mut [u8]) -> std::io::Result<usize> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::BrokenPipe,
+            "pipe closed",
+        ))
+    }
+}
+
+impl Write for FailingIo {
+    fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::BrokenPipe,
+            "pipe closed",
+        ))
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_custom_read_error() {
+    let xml = r#"<example><hash>not a hash</hash></example>"#;
+    let error = ex_em_ell::from_str::<Example>(xml).expect_err("Should fail on the invalid hash");
+
+    assert!(matches!(error, XmlReadError::Custom { .. }));
+    insta::assert_snapshot!(error.to_string(), @r###"Invalid hash at /example/hash (1:26): "not a hash" is not a SHA-1 hash"###);
+}
+
+#[test]
+fn test_custom_write_error() {
+    let example = Example {
+        hash: Hash("abc".to_string()),
+    };
+    let error = ex_em_ell::to_string(&example).expect_err("Should fail on the invalid hash");
+
+    assert!(matches!(error, XmlWriteError::Custom { .. }));
+    insta::assert_snapshot!(error.to_string(), @r###"Invalid hash: "abc" is not a SHA-1 hash"###);
+}
+
+#[test]
+fn test_read_io_error() {
+    let error = ex_em_ell::from_reader::<Example, _>(FailingIo)
+        .expect_err("Should fail to read from the reader");
+
+    match error {
+        XmlReadError::IoError { error, .. } => {
+            assert_eq!(error.kind(), std::io::ErrorKind::BrokenPipe)
+        }
+        other => panic!("Expected an I/O error, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_write_io_error() {
+    let example = Example {
+        hash: Hash("0123456789abcdef0123456789abcdef01234567".to_string()),
+    };
+    let error =
+        ex_em_ell::to_writer(FailingIo, &example).expect_err("Should fail to write to the writer");
+
+    match error {
+        XmlWriteError::IoError { error, .. } => {
+            assert_eq!(error.kind(), std::io::ErrorKind::BrokenPipe)
+        }
+        other => panic!("Expected an I/O error, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_invalid_characters_in_text() {
+    let commented = Commented {
+        comments: Vec::new(),
+        field: "null \u{0} character".to_string(),
+    };
+    let error = ex_em_ell::to_string(&commented).expect_err("Should fail on the null character");
+
+    insta::assert_snapshot!(error.to_string(), @r###"Cannot write "null \0 character" in field, as it contains characters that are not allowed in XML"###);
+}
+
+#[test]
+fn test_characters_checked_against_xml_version() {
+    let commented = Commented {
+        comments: Vec::new(),
+        field: "next \u{85} line, delete \u{7f}".to_string(),
+    };
+    ex_em_ell::to_string(&commented).expect("Should write the characters in XML 1.0");
+
+    let options = WriteOptions::new().xml_version(XmlVersion::Version11);
+    let error = ex_em_ell::to_string_with_options(&commented, options)
+        .expect_err("Should fail on the restricted character in XML 1.1");
+
+    assert!(matches!(error, XmlWriteError::InvalidCharacters { .. }));
+}
+
+#[test]
+fn test_invalid_characters_in_comment() {
+    let commented = Commented {
+        comments: vec!["a -- b".to_string()],
+        field: "value".to_string(),
+    };
+    let error = ex_em_ell::to_string(&commented).expect_err("Should fail on the double hyphen");
+
+    assert!(matches!(error, XmlWriteError::InvalidCharacters { .. }));
+}
+
+#[test]
+fn test_invalid_name() {
+    let mut output = Vec::new();
+    let mut writer = XmlRsSink::new(&mut output, &WriteOptions::default());
+    let error = "value"
+        .to_string()
+        .to_xml_element(&mut writer, "not a name")
+        .expect_err("Should fail on the name with spaces");
+
+    insta::assert_snapshot!(error.to_string(), @r###"Cannot write "not a name" as an XML name in not a name"###);
+}
@@ -0,0 +1,178 @@
+use ex_em_ell::errors::{DoctypeViolation, ReadLimit, XmlReadError};
+use ex_em_ell::{DoctypePolicy, ReadOptions};
+
+#[derive(Debug, PartialEq, ex_em_ell::FromXmlDocument)]
+struct Example {
+    field: String,
+}
+
+fn read(xml: &str, doctype: DoctypePolicy) -> Result<Example, XmlReadError> {
+    ex_em_ell::from_reader_with_options(xml.as_bytes(), ReadOptions::default().doctype(doctype))
+}
+
+fn violation(error: XmlReadError) -> DoctypeViolation {
+    match error {
+        XmlReadError::DoctypeNotAllowed { violation, .. } => violation,
+        other => panic!("Expected a DOCTYPE error, got {other:?}"),
+    }
+}
+
+const WITHOUT_ENTITIES: &str =
+    r#"<!DOCTYPE example [<!ELEMENT example (field)>]><example><field>value</field></example>"#;
+
+const WITH_ENTITY: &str =
+    r#"<!DOCTYPE example [<!ENTITY name "expanded">]><example><field>&name;</field></example>"#;
+
+const WITH_EXTERNAL_ENTITY: &str = r#"<!DOCTYPE example [<!ENTITY name SYSTEM "file:///etc/passwd">]><example><field>&name;</field></example>"#;
+
+const BILLION_LAUGHS: &str = r#"<!DOCTYPE example [
+  <!ENTITY lol "lol">
+  <!ENTITY lol1 "&lol;&lol;&lol;&lol;&lol;&lol;&lol;&lol;&lol;&lol;">
+  <!ENTITY lol2 "&lol1;&lol1;&lol1;&lol1;&lol1;&lol1;&lol1;&lol1;&lol1;&lol1;">
+  <!ENTITY lol3 "&lol2;&lol2;&lol2;&lol2;&lol2;&lol2;&lol2;&lol2;&lol2;&lol2;">
+  <!ENTITY lol4 "&lol3;&lol3;&lol3;&lol3;&lol3;&lol3;&lol3;&lol3;&lol3;&lol3;">
+]><example><field>&lol4;</field></example>"#;
+
+#[test]
+fn test_default_allows_doctype_without_entities() {
+    let example =
+        ex_em_ell::from_str::<Example>(WITHOUT_ENTITIES).expect("Should allow the DOCTYPE");
+
+    assert_eq!(example.field, "value");
+}
+
+#[test]
+fn test_default_rejects_entities() {
+    let error = ex_em_ell::from_str::<Example>(WITH_ENTITY).expect_err("Should reject the entity");
+
+    insta::assert_snapshot!(error.to_string(), @"Document declares entities, which is not allowed by the read options at / (1:47)");
+}
+
+#[test]
+fn test_reject_doctype() {
+    let error =
+        read(WITHOUT_ENTITIES, DoctypePolicy::Reject).expect_err("Should reject the DOCTYPE");
+
+    assert_eq!(violation(error), DoctypeViolation::Doctype);
+}
+
+#[test]
+fn test_allow_internal_entities() {
+    let example = read(WITH_ENTITY, DoctypePolicy::allow_internal_entities())
+        .expect("Should expand the entity");
+
+    assert_eq!(example.field, "expanded");
+}
+
+#[test]
+fn test_external_entities_are_rejected() {
+    let error = read(
+        WITH_EXTERNAL_ENTITY,
+        DoctypePolicy::allow_internal_entities(),
+    )
+    .expect_err("Should reject the external entity");
+
+    assert_eq!(
+        violation(error),
+        DoctypeViolation::ExternalEntityDeclaration
+    );
+}
+
+#[test]
+fn test_entity_expansion_budget() {
+    let policy = DoctypePolicy::AllowInternalEntities {
+        max_expansion_length: 1_000,
+        max_expansion_depth: 10,
+    };
+    let error = read(BILLION_LAUGHS, policy).expect_err("Should stop expanding the entities");
+
+    match error {
+        XmlReadError::LimitExceeded { limit, .. } => assert_eq!(
+            limit,
+            ReadLimit::EntityExpansion {
+                max_expansion_length: 1_000,
+                max_expansion_depth: 10,
+            }
+        ),
+        other => panic!("Expected a limit error, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_billion_laughs_rejected_by_default() {
+    let error =
+        ex_em_ell::from_str::<Example>(BILLION_LAUGHS).expect_err("Should reject the entities");
+
+    assert_eq!(violation(error), DoctypeViolation::EntityDeclaration);
+}
+
+#[test]
+fn test_declarations_in_comments_are_ignored() {
+    let xml = r#"<!DOCTYPE example [<!-- <!ENTITY name SYSTEM "file:///etc/passwd"> -->]><example><field>value</field></example>"#;
+    let example = ex_em_ell::from_str::<Example>(xml).expect("Should ignore the comment");
+
+    assert_eq!(example.field, "value");
+}
+
+#[test]
+fn test_declarations_in_literals_are_ignored() {
+    let xml = r#"<!DOCTYPE example [<!ENTITY note "<!ENTITY inner SYSTEM 'file:///etc/passwd'>">]><example><field>value</field></example>"#;
+    let example = read(xml, DoctypePolicy::allow_internal_entities())
+        .expect("Should only find the internal entity");
+
+    assert_eq!(example.field, "value");
+}
+
+#[test]
+fn test_recursive_entities_exceed_budget() {
+    let xml = r#"<!DOCTYPE example [<!ENTITY a "&b;"><!ENTITY b "&a;">]><example><field>&a;</field></example>"#;
+    let error = read(xml, DoctypePolicy::allow_internal_entities())
+        .expect_err("Should not expand the recursive entities");
+
+    assert!(matches!(
+        error,
+        XmlReadError::LimitExceeded {
+            limit: ReadLimit::EntityExpansion { .. },
+            ..
+        }
+    ));
+}
+
+#[test]
+fn test_entity_referred_to_many_times() {
+    let xml = format!(
+        r#"<!DOCTYPE example [<!ENTITY a "a"><!ENTITY b "{}">]><example><field>&b;</field></example>"#,
+        "&a;".repeat(20)
+    );
+    let example = read(&xml, DoctypePolicy::allow_internal_entities())
+        .expect("Should expand the entities within the budget");
+
+    assert_eq!(example.field, "a".repeat(20));
+}
+
+#[test]
+fn test_expansion_budget_covers_the_whole_document() {
+    let policy = DoctypePolicy::AllowInternalEntities {
+        max_expansion_length: 1_000,
+        max_expansion_depth: 10,
+    };
+    // Each reference expands to 10 characters, but 20 bytes
+    let document = |references: usize| {
+        format!(
+            r#"<!DOCTYPE example [<!ENTITY e "éééééééééé">]><example><field>{}</field></example>"#,
+            "&e;".repeat(references)
+        )
+    };
+
+    let example = read(&document(100), policy).expect("Should stay within the budget");
+    assert_eq!(example.field, "é".repeat(1_000));
+
+    let error = read(&document(101), policy).expect_err("Should go over the budget");
+    assert!(matches!(
+        error,
+        XmlReadError::LimitExceeded {
+            limit: ReadLimit::EntityExpansion { .. },
+            ..
+        }
+    ));
+}
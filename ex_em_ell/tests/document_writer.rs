@@ -0,0 +1,117 @@
+use ex_em_ell::errors::XmlWriteError;
+use ex_em_ell::events::WriteEvent;
+use ex_em_ell::XmlDocumentWriter;
+
+#[derive(Debug, PartialEq, ex_em_ell::ToXmlDocument)]
+#[ex_em_ell(namespace = "urn:example")]
+struct Bom {
+    components: Vec<Component>,
+    serial_number: u64,
+}
+
+#[derive(Debug, PartialEq, ex_em_ell::ToXmlElement, ex_em_ell::NamedXmlElement)]
+#[ex_em_ell(name = "component")]
+struct Component {
+    #[ex_em_ell(attribute)]
+    version: u32,
+    name: String,
+}
+
+fn components() -> impl Iterator<Item = Component> {
+    (1..=3).map(|version| Component {
+        version,
+        name: format!("component {version}"),
+    })
+}
+
+#[test]
+fn test_matches_derived_writer() {
+    let mut writer = XmlDocumentWriter::new(Vec::new()).expect("Should write the declaration");
+    writer
+        .start_element_with_namespace("bom", "urn:example")
+        .expect("Should open the root");
+    writer
+        .start_element("components")
+        .expect("Should open the components");
+    for component in components() {
+        writer
+            .write_element(&component, "component")
+            .expect("Should write the component");
+    }
+    writer.end_element().expect("Should close the components");
+    writer
+        .write_element(&12345u64, "serialNumber")
+        .expect("Should write the serial number");
+    writer.end_element().expect("Should close the root");
+    let output = writer.finish().expect("Should finish the document");
+
+    let bom = Bom {
+        components: components().collect(),
+        serial_number: 12345,
+    };
+    assert_eq!(
+        String::from_utf8(output).expect("Should be UTF-8"),
+        ex_em_ell::to_string(&bom).expect("Should write the BOM")
+    );
+}
+
+#[test]
+fn test_finish_with_unclosed_elements() {
+    let mut writer = XmlDocumentWriter::new(Vec::new()).expect("Should write the declaration");
+    writer.start_element("bom").expect("Should open the root");
+    writer
+        .start_element("components")
+        .expect("Should open the components");
+
+    let error = writer
+        .finish()
+        .expect_err("Should fail with the elements open");
+    insta::assert_snapshot!(error.to_string(), @"Finished writing the document with the elements bom, components still open");
+}
+
+#[test]
+fn test_end_without_open_element() {
+    let mut writer = XmlDocumentWriter::new(Vec::new()).expect("Should write the declaration");
+
+    assert!(matches!(
+        writer.end_element(),
+        Err(XmlWriteError::NoOpenElement)
+    ));
+    assert!(matches!(
+        writer.write_element(&1u32, "value"),
+        Err(XmlWriteError::NoOpenElement)
+    ));
+}
+
+#[test]
+fn test_drop_with_unclosed_elements() {
+    let mut output = Vec::new();
+    let mut writer = XmlDocumentWriter::new(&mut output).expect("Should write the declaration");
+    writer.start_element("bom").expect("Should open the root");
+    writer
+        .start_element("components")
+        .expect("Should open the list");
+    drop(writer);
+
+    insta::assert_snapshot!(String::from_utf8(output).expect("Should be UTF-8"), @r###"<?xml version="1.0" encoding="utf-8"?><bom><components /></bom>"###);
+}
+
+#[test]
+fn test_start_element_with_attributes() {
+    let mut writer = XmlDocumentWriter::new(Vec::new()).expect("Should write the declaration");
+    writer
+        .start_element_with(
+            WriteEvent::start_element("bom")
+                .default_ns("urn:example")
+                .attr(
+                    "serialNumber",
+                    "urn:uuid:3e671687-395b-41f5-a30f-a58921a69b79",
+                )
+                .attr("version", "1"),
+        )
+        .expect("Should open the root");
+    writer.end_element().expect("Should close the root");
+    let output = writer.finish().expect("Should finish the document");
+
+    insta::assert_snapshot!(String::from_utf8(output).expect("Should be UTF-8"), @r###"<?xml version="1.0" encoding="utf-8"?><bom xmlns="urn:example" serialNumber="urn:uuid:3e671687-395b-41f5-a30f-a58921a69b79" version="1" />"###);
+}
@@ -0,0 +1,139 @@
+use ex_em_ell::errors::{ReadLimit, XmlReadError};
+use ex_em_ell::ReadOptions;
+
+#[derive(Debug, PartialEq, ex_em_ell::FromXmlDocument)]
+struct Example {
+    #[ex_em_ell(attribute)]
+    version: u32,
+    children: Vec<ExampleChild>,
+}
+
+#[derive(Debug, PartialEq, ex_em_ell::FromXmlElement, ex_em_ell::NamedXmlElement)]
+#[ex_em_ell(name = "child")]
+struct ExampleChild {
+    field: String,
+}
+
+/// A recursive type, whose reader recurses once for every level of nesting
+#[derive(Debug, PartialEq)]
+struct Nested(Option<Box<Nested>>);
+
+impl ex_em_ell::FromXmlDocument for Nested {
+    fn from_xml_document<R: ex_em_ell::backend::XmlEventSource>(
+        reader: &mut ex_em_ell::XmlEventReader<R>,
+    ) -> Result<Self, XmlReadError> {
+        fn read_nested<R: ex_em_ell::backend::XmlEventSource>(
+            reader: &mut ex_em_ell::XmlEventReader<R>,
+        ) -> Result<Nested, XmlReadError> {
+            match ex_em_ell::xml_utils::next_non_misc_event(reader)? {
+                ex_em_ell::events::XmlEvent::StartElement { .. } => {
+                    let inner = read_nested(reader)?;
+                    Ok(Nested(Some(Box::new(inner))))
+                }
+                _ => Ok(Nested(None)),
+            }
+        }
+
+        reader.next_event()?;
+        read_nested(reader)
+    }
+}
+
+fn read_with<T: ex_em_ell::FromXmlDocument>(xml: &str, options: ReadOptions) -> XmlReadError {
+    match ex_em_ell::from_reader_with_options::<T, _>(xml.as_bytes(), options) {
+        Ok(_) => panic!("Should exceed the limit"),
+        Err(error) => error,
+    }
+}
+
+fn limit(error: &XmlReadError) -> ReadLimit {
+    match error {
+        XmlReadError::LimitExceeded { limit, .. } => *limit,
+        other => panic!("Expected a limit error, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_depth_limit() {
+    let xml = format!("{}{}", "<a>".repeat(1_000), "</a>".repeat(1_000));
+    let error = read_with::<Nested>(&xml, ReadOptions::default());
+
+    assert_eq!(limit(&error), ReadLimit::Depth(128));
+}
+
+#[test]
+fn test_element_limit() {
+    let xml = format!(
+        r#"<example version="1"><children>{}</children></example>"#,
+        "<child><field>value</field></child>".repeat(10)
+    );
+    let error = read_with::<Example>(&xml, ReadOptions::default().max_elements(10));
+
+    insta::assert_snapshot!(error.to_string(), @"Exceeded the limit of 10 elements while reading children at /example/children (1:172)");
+}
+
+#[test]
+fn test_text_limit() {
+    let xml = format!(
+        r#"<example version="1"><children><child><field>{}</field></child></children></example>"#,
+        "a".repeat(100)
+    );
+    let error = read_with::<Example>(&xml, ReadOptions::default().max_text_length(50));
+
+    assert_eq!(limit(&error), ReadLimit::TextLength(50));
+}
+
+#[test]
+fn test_text_limit_across_events() {
+    let xml = format!(
+        r#"<example version="1"><children><child><field>{0}<!-- split -->{0}</field></child></children></example>"#,
+        "a".repeat(30)
+    );
+    let error = read_with::<Example>(&xml, ReadOptions::default().max_text_length(50));
+
+    assert_eq!(limit(&error), ReadLimit::TextLength(50));
+}
+
+#[test]
+fn test_attribute_limit() {
+    let xml = r#"<example version="1" a="1" b="2" c="3"><children /></example>"#;
+    let error = read_with::<Example>(xml, ReadOptions::default().max_attributes(3));
+
+    assert_eq!(limit(&error), ReadLimit::Attributes(3));
+}
+
+#[test]
+fn test_attribute_limit_far_exceeded() {
+    let attributes: String = (0..100).map(|i| format!(" a{}=\"{}\"", i, i)).collect();
+    let xml = format!(
+        r#"<example version="1"{}><children /></example>"#,
+        attributes
+    );
+    let error = read_with::<Example>(&xml, ReadOptions::default().max_attributes(3));
+
+    assert_eq!(limit(&error), ReadLimit::Attributes(3));
+}
+
+#[test]
+fn test_processing_instruction_limit() {
+    let xml = format!(
+        r#"<?target {}?><example version="1"><children /></example>"#,
+        "a".repeat(100)
+    );
+    let error = read_with::<Example>(&xml, ReadOptions::default().max_text_length(50));
+
+    assert_eq!(limit(&error), ReadLimit::TextLength(50));
+}
+
+#[test]
+fn test_within_limits() {
+    let xml = r#"<example version="1"><children><child><field>value</field></child><child><field>second</field></child></children></example>"#;
+    let options = ReadOptions::default()
+        .max_depth(4)
+        .max_elements(6)
+        .max_text_length(6)
+        .max_attributes(1);
+
+    ex_em_ell::from_reader_with_options::<Example, _>(xml.as_bytes(), options)
+        .expect("Should read a document within the limits");
+}
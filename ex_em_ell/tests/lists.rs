@@ -1,3 +1,8 @@
+// The example files are named in the `expect` messages of the snapshot tests
+#![allow(clippy::expect_fun_call)]
+
+mod common;
+
 #[derive(Debug, PartialEq, ex_em_ell::FromXmlDocument, ex_em_ell::ToXmlDocument)]
 struct Example {
     children: Vec<ExampleChild>,
@@ -22,3 +27,5 @@ fn test_example_xmls() {
         insta::assert_snapshot!(round_trip);
     });
 }
+
+common::test_example_xmls_quick_xml!(Example, "data/lists/valid_*.xml");
@@ -0,0 +1,236 @@
+use ex_em_ell::backend::XmlEventSource;
+use ex_em_ell::errors::XmlReadError;
+use ex_em_ell::events::{Namespace, XmlAttribute, XmlEvent, XmlName};
+use ex_em_ell::{FromXmlElement, NamedXmlElement, XmlEventReader};
+
+#[derive(Debug, PartialEq, ex_em_ell::FromXmlDocument)]
+struct Drawing {
+    shapes: Vec<Shape>,
+}
+
+#[derive(Debug, PartialEq)]
+enum Shape {
+    Circle(Circle),
+    Rectangle(Rectangle),
+}
+
+#[derive(Debug, PartialEq, ex_em_ell::FromXmlElement)]
+struct Circle {
+    radius: f64,
+}
+
+#[derive(Debug, PartialEq, ex_em_ell::FromXmlElement)]
+struct Rectangle {
+    width: f64,
+    height: f64,
+}
+
+impl NamedXmlElement for Shape {
+    fn xml_element_name() -> &'static str {
+        "shape"
+    }
+}
+
+impl FromXmlElement for Shape {
+    fn from_xml_element<R: XmlEventSource>(
+        reader: &mut XmlEventReader<R>,
+        element_name: &XmlName,
+        element_attributes: &[XmlAttribute],
+        element_namespace: &Namespace,
+    ) -> Result<Self, XmlReadError> {
+        reader
+            .attempt(|reader| {
+                Circle::from_xml_element(
+                    reader,
+                    element_name,
+                    element_attributes,
+                    element_namespace,
+                )
+            })
+            .map(Shape::Circle)
+            .or_else(|_| {
+                Rectangle::from_xml_element(
+                    reader,
+                    element_name,
+                    element_attributes,
+                    element_namespace,
+                )
+                .map(Shape::Rectangle)
+            })
+    }
+}
+
+const DRAWING: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<drawing>
+  <shapes>
+    <shape><radius>2</radius></shape>
+    <shape><width>3</width><height>4</height></shape>
+    <shape><radius>5</radius></shape>
+  </shapes>
+</drawing>"#;
+
+/// Read until the start tag of the first shape
+fn reader_at_first_shape() -> XmlEventReader<impl XmlEventSource> {
+    let mut reader = XmlEventReader::new(DRAWING.as_bytes());
+    reader
+        .seek_element("/drawing/shapes")
+        .expect("Should read the document")
+        .expect("Should find the shapes");
+    reader
+}
+
+fn next_start_element<R: XmlEventSource>(reader: &mut XmlEventReader<R>) -> XmlName {
+    match reader.next_event().expect("Should read the next event") {
+        XmlEvent::StartElement { name, .. } => name,
+        unexpected => panic!("Should be a start tag: {unexpected:?}"),
+    }
+}
+
+#[test]
+fn test_attempt_each_variant() {
+    let drawing: Drawing = ex_em_ell::from_str(DRAWING).expect("Should read the drawing");
+
+    assert_eq!(
+        drawing.shapes,
+        vec![
+            Shape::Circle(Circle { radius: 2.0 }),
+            Shape::Rectangle(Rectangle {
+                width: 3.0,
+                height: 4.0
+            }),
+            Shape::Circle(Circle { radius: 5.0 }),
+        ]
+    );
+}
+
+#[test]
+fn test_rewind_discards_collected_errors() {
+    let drawing: Drawing = ex_em_ell::from_reader_collect_errors(DRAWING.as_bytes())
+        .expect("Should only report errors that were not rewound past");
+
+    assert_eq!(drawing.shapes.len(), 3);
+}
+
+#[test]
+fn test_rewind_restores_path() {
+    let mut reader = reader_at_first_shape();
+    next_start_element(&mut reader);
+    let name = next_start_element(&mut reader);
+    assert_eq!(name.local_name, "radius");
+    reader
+        .skip_element(reader.depth())
+        .expect("Should skip the radius");
+    reader
+        .next_event()
+        .expect("Should read the end of the shape");
+
+    let mark = reader.mark();
+    let name = next_start_element(&mut reader);
+    assert_eq!(reader.path().to_string(), "/drawing/shapes/shape[2]");
+    let error = Circle::from_xml_element(&mut reader, &name, &[], &Namespace::default())
+        .expect_err("Should not read a rectangle as a circle");
+    insta::assert_snapshot!(error.to_string(), @"Found `<width>` while reading shape, expected `<radius>` at /drawing/shapes/shape[2]/width (5:12)");
+
+    reader.rewind(mark);
+    let name = next_start_element(&mut reader);
+    assert_eq!(reader.path().to_string(), "/drawing/shapes/shape[2]");
+    let rectangle = Rectangle::from_xml_element(&mut reader, &name, &[], &Namespace::default())
+        .expect("Should read the rectangle after rewinding");
+    assert_eq!(
+        rectangle,
+        Rectangle {
+            width: 3.0,
+            height: 4.0
+        }
+    );
+
+    // The events after the mark are read from the source once the buffered ones have been replayed
+    assert_eq!(next_start_element(&mut reader).local_name, "shape");
+    assert_eq!(reader.path().to_string(), "/drawing/shapes/shape[3]");
+    assert_eq!(reader.position().line, 6);
+}
+
+#[test]
+fn test_nested_marks() {
+    let mut reader = reader_at_first_shape();
+
+    let outer = reader.mark();
+    next_start_element(&mut reader);
+    let inner = reader.mark();
+    assert_eq!(next_start_element(&mut reader).local_name, "radius");
+    reader.rewind(inner);
+    assert_eq!(next_start_element(&mut reader).local_name, "radius");
+    reader.rewind(outer);
+
+    assert_eq!(next_start_element(&mut reader).local_name, "shape");
+    assert_eq!(reader.path().to_string(), "/drawing/shapes/shape");
+    assert_eq!(next_start_element(&mut reader).local_name, "radius");
+}
+
+#[test]
+fn test_dropped_mark_is_released() {
+    let mut reader = reader_at_first_shape();
+
+    let outer = reader.mark();
+    next_start_element(&mut reader);
+    let inner = reader.mark();
+    assert_eq!(next_start_element(&mut reader).local_name, "radius");
+    drop(inner);
+    reader.rewind(outer);
+    assert_eq!(next_start_element(&mut reader).local_name, "shape");
+
+    // Once the only mark is dropped, marks set later are rewound to where they were set
+    let dropped = reader.mark();
+    assert_eq!(next_start_element(&mut reader).local_name, "radius");
+    drop(dropped);
+    reader
+        .skip_element(reader.depth())
+        .expect("Should skip the radius");
+    reader
+        .next_event()
+        .expect("Should read the end of the shape");
+    let mark = reader.mark();
+    next_start_element(&mut reader);
+    assert_eq!(next_start_element(&mut reader).local_name, "width");
+    reader.rewind(mark);
+    assert_eq!(next_start_element(&mut reader).local_name, "shape");
+    assert_eq!(next_start_element(&mut reader).local_name, "width");
+}
+
+#[test]
+#[should_panic(
+    expected = "marks have to be rewound to or released in the reverse order they were set"
+)]
+fn test_marks_out_of_order() {
+    let mut reader = reader_at_first_shape();
+
+    let outer = reader.mark();
+    next_start_element(&mut reader);
+    let inner = reader.mark();
+    next_start_element(&mut reader);
+    reader.rewind(outer);
+    reader.rewind(inner);
+}
+
+#[test]
+fn test_peek_event() {
+    let mut reader = reader_at_first_shape();
+
+    let peeked = reader.peek_event().expect("Should peek the shape");
+    assert!(matches!(&peeked, XmlEvent::StartElement { name, .. } if name.local_name == "shape"));
+    assert_eq!(reader.path().to_string(), "/drawing/shapes");
+
+    assert_eq!(reader.next_event().expect("Should read the shape"), peeked);
+    assert_eq!(reader.path().to_string(), "/drawing/shapes/shape");
+}
+
+#[test]
+fn test_skip_subtree() {
+    let mut reader = reader_at_first_shape();
+
+    reader.skip_subtree().expect("Should skip the first shape");
+    next_start_element(&mut reader);
+
+    assert_eq!(reader.path().to_string(), "/drawing/shapes/shape[2]");
+    assert_eq!(next_start_element(&mut reader).local_name, "width");
+}
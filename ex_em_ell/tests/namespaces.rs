@@ -0,0 +1,87 @@
+#[derive(Debug, PartialEq, ex_em_ell::FromXmlDocument, ex_em_ell::ToXmlDocument)]
+#[ex_em_ell(namespace = "urn:example")]
+struct Example {
+    field: String,
+    child: ExampleChild,
+}
+
+#[derive(Debug, PartialEq, ex_em_ell::FromXmlElement, ex_em_ell::ToXmlElement)]
+struct ExampleChild {
+    field: String,
+}
+
+#[test]
+fn test_example_xmls() {
+    insta::glob!("data/namespaces/valid_*.xml", |path| {
+        let file =
+            std::fs::File::open(path).unwrap_or_else(|_| panic!("Failed to read file: {path:?}"));
+        let example: Example = ex_em_ell::from_reader(&file)
+            .unwrap_or_else(|_| panic!("Failed to parse the XML file: {path:?}"));
+
+        let round_trip = ex_em_ell::to_string_pretty(&example).expect("Failed to output XML");
+        insta::assert_snapshot!(round_trip);
+    });
+}
+
+#[test]
+fn test_extension_element_is_not_a_field() {
+    let xml = r#"<example xmlns="urn:example" xmlns:ext="urn:extension"><ext:field>extension</ext:field><child><field>value</field></child></example>"#;
+    let error = ex_em_ell::from_reader::<Example, _>(xml.as_bytes())
+        .expect_err("Should not read the extension element as the field");
+
+    assert!(matches!(
+        error,
+        ex_em_ell::errors::XmlReadError::UnexpectedElementReadError { .. }
+    ));
+}
+
+#[test]
+fn test_wrong_document_namespace() {
+    let xml = r#"<example xmlns="urn:other"><field>value</field><child><field>value</field></child></example>"#;
+    let error = ex_em_ell::from_reader::<Example, _>(xml.as_bytes())
+        .expect_err("Should fail with the wrong namespace");
+
+    insta::assert_snapshot!(error.to_string(), @"Expected document to be in the form urn:example, but received urn:other at /example (1:27)");
+}
+
+#[derive(Debug, PartialEq, ex_em_ell::FromXmlDocument, ex_em_ell::ToXmlDocument)]
+#[ex_em_ell(
+    rename = "example",
+    namespace = "urn:example",
+    prefix = "ex",
+    element_form_default = "unqualified"
+)]
+struct UnqualifiedExample {
+    field: String,
+    child: UnqualifiedChild,
+}
+
+#[derive(Debug, PartialEq, ex_em_ell::FromXmlElement, ex_em_ell::ToXmlElement)]
+#[ex_em_ell(element_form_default = "unqualified")]
+struct UnqualifiedChild {
+    field: String,
+}
+
+#[test]
+fn test_unqualified_element_form() {
+    let file = std::fs::File::open("tests/data/namespaces/valid_unqualified.xml")
+        .expect("Should open the unqualified example");
+    let example: UnqualifiedExample =
+        ex_em_ell::from_reader(file).expect("Should read the unqualified children");
+
+    let xml = ex_em_ell::to_string(&example).expect("Should write the example");
+    insta::assert_snapshot!(xml, @r###"<?xml version="1.0" encoding="utf-8"?><ex:example xmlns:ex="urn:example"><field>value</field><child><field>value</field></child></ex:example>"###);
+    assert_eq!(
+        ex_em_ell::from_str::<UnqualifiedExample>(&xml).expect("Should read the written example"),
+        example
+    );
+}
+
+#[test]
+fn test_qualified_child_of_unqualified_form() {
+    let xml = r#"<ex:example xmlns:ex="urn:example"><ex:field>value</ex:field><child><field>value</field></child></ex:example>"#;
+    let error = ex_em_ell::from_str::<UnqualifiedExample>(xml)
+        .expect_err("Should not read a qualified child as an unqualified field");
+
+    insta::assert_snapshot!(error.to_string(), @"Found `<ex:field>` while reading {urn:example}ex:example, expected one of `<field>`, `<child>` at /ex:example/ex:field (1:36)");
+}
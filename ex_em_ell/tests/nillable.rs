@@ -0,0 +1,120 @@
+use ex_em_ell::Nillable;
+
+#[derive(Debug, PartialEq, ex_em_ell::FromXmlDocument, ex_em_ell::ToXmlDocument)]
+struct Catalog {
+    products: Vec<Product>,
+}
+
+#[derive(
+    Debug, PartialEq, ex_em_ell::FromXmlElement, ex_em_ell::ToXmlElement, ex_em_ell::NamedXmlElement,
+)]
+struct Product {
+    name: String,
+    price: Nillable<f64>,
+    dimensions: Nillable<Dimensions>,
+}
+
+#[derive(Debug, PartialEq, ex_em_ell::FromXmlElement, ex_em_ell::ToXmlElement)]
+struct Dimensions {
+    width: u32,
+    height: u32,
+}
+
+#[test]
+fn test_example_xmls() {
+    insta::glob!("data/nillable/valid_*.xml", |path| {
+        let file =
+            std::fs::File::open(path).unwrap_or_else(|_| panic!("Failed to read file: {path:?}"));
+        let catalog: Catalog = ex_em_ell::from_reader(&file)
+            .unwrap_or_else(|_| panic!("Failed to parse the XML file: {path:?}"));
+
+        let round_trip = ex_em_ell::to_string_pretty(&catalog).expect("Failed to output XML");
+        insta::assert_snapshot!(round_trip);
+    });
+}
+
+#[test]
+fn test_absent_nil_and_present_are_distinct() {
+    let xml = r#"<catalog xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance"><products><product><name>Hammer</name><price>12.5</price></product><product><name>Gift card</name><price xsi:nil="true"/></product><product><name>Sample</name></product></products></catalog>"#;
+    let catalog: Catalog = ex_em_ell::from_str(xml).expect("Should read the catalog");
+
+    let prices: Vec<_> = catalog
+        .products
+        .iter()
+        .map(|product| product.price.clone())
+        .collect();
+    assert_eq!(
+        prices,
+        vec![Nillable::Present(12.5), Nillable::Nil, Nillable::Absent]
+    );
+    assert!(catalog
+        .products
+        .iter()
+        .all(|product| product.dimensions.is_absent()));
+}
+
+#[test]
+fn test_write_declares_namespace() {
+    let catalog = Catalog {
+        products: vec![Product {
+            name: "Gift card".to_string(),
+            price: Nillable::Nil,
+            dimensions: Nillable::Absent,
+        }],
+    };
+    let xml = ex_em_ell::to_string(&catalog).expect("Should write the catalog");
+
+    insta::assert_snapshot!(xml, @r###"<?xml version="1.0" encoding="utf-8"?><catalog><products><product><name>Gift card</name><price xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xsi:nil="true" /></product></products></catalog>"###);
+    assert_eq!(
+        ex_em_ell::from_str::<Catalog>(&xml).expect("Should read the written catalog"),
+        catalog
+    );
+}
+
+#[test]
+fn test_nil_element_with_content() {
+    let xml = r#"<catalog xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance"><products><product><name>Gift card</name><price xsi:nil="true">10</price></product></products></catalog>"#;
+    let error = ex_em_ell::from_str::<Catalog>(xml).expect_err("Should not read a nil price");
+
+    insta::assert_snapshot!(error.to_string(), @"Found text while reading price at /catalog/products/product/price (1:127)");
+}
+
+#[test]
+fn test_invalid_nil() {
+    let xml = r#"<catalog xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance"><products><product><name>Gift card</name><price xsi:nil="yes"/></product></products></catalog>"#;
+    let error = ex_em_ell::from_str::<Catalog>(xml).expect_err("Should not parse the xsi:nil");
+
+    insta::assert_snapshot!(error.to_string(), @"Could not parse yes as xs:boolean on price/@xsi:nil at /catalog/products/product/price (1:105)");
+}
+
+#[test]
+fn test_unqualified_nil_attribute() {
+    let xml = r#"<catalog><products><product><name>Gift card</name><price nil="true"/></product></products></catalog>"#;
+    let error = ex_em_ell::from_str::<Catalog>(xml)
+        .expect_err("Should only treat xsi:nil as making the element nil");
+
+    insta::assert_snapshot!(error.to_string(), @"Could not parse  as xs:double on price at /catalog/products/product/price (1:51)");
+}
+
+#[cfg(feature = "quick-xml")]
+#[test]
+fn test_borrowed() {
+    #[derive(Debug, PartialEq, ex_em_ell::FromXmlDocumentBorrowed)]
+    struct Note<'a> {
+        author: Nillable<&'a str>,
+        title: Nillable<&'a str>,
+        body: Nillable<&'a str>,
+    }
+
+    let xml = r#"<note xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance"><author xsi:nil="true"/><body>Hello</body></note>"#;
+    let note: Note = ex_em_ell::from_str_borrowed(xml).expect("Should read the note");
+
+    assert_eq!(
+        note,
+        Note {
+            author: Nillable::Nil,
+            title: Nillable::Absent,
+            body: Nillable::Present("Hello"),
+        }
+    );
+}
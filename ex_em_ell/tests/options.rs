@@ -0,0 +1,76 @@
+use ex_em_ell::ReadOptions;
+
+#[derive(Debug, PartialEq, ex_em_ell::FromXmlDocument)]
+#[ex_em_ell(namespace = "urn:example")]
+struct Example {
+    #[ex_em_ell(comments)]
+    comments: Vec<String>,
+
+    field: String,
+}
+
+#[test]
+fn test_from_str_defaults() {
+    let xml = r#"<example xmlns="urn:example"><!--note--><field>  value  </field></example>"#;
+    let example: Example = ex_em_ell::from_str(xml).expect("Failed to parse the XML");
+
+    assert_eq!(
+        example,
+        Example {
+            comments: vec!["note".to_string()],
+            field: "value".to_string(),
+        }
+    );
+}
+
+#[test]
+fn test_from_slice_matches_from_str() {
+    let xml = r#"<example xmlns="urn:example"><field>value</field></example>"#;
+    let from_str: Example = ex_em_ell::from_str(xml).expect("Failed to parse the XML");
+    let from_slice: Example =
+        ex_em_ell::from_slice(xml.as_bytes()).expect("Failed to parse the XML");
+
+    assert_eq!(from_str, from_slice);
+}
+
+#[test]
+fn test_whitespace_and_comment_options() {
+    let xml = r#"<example xmlns="urn:example"><!--note--><field>  value  </field></example>"#;
+    let options = ReadOptions::new()
+        .trim_whitespace(false)
+        .ignore_comments(true);
+    let example: Example = ex_em_ell::from_reader_with_options(xml.as_bytes(), options)
+        .expect("Failed to parse the XML");
+
+    assert_eq!(
+        example,
+        Example {
+            comments: Vec::new(),
+            field: "  value  ".to_string(),
+        }
+    );
+}
+
+#[test]
+fn test_entity_options() {
+    let xml = r#"<example xmlns="urn:example"><field>&product;</field></example>"#;
+    ex_em_ell::from_str::<Example>(xml).expect_err("Should fail on an unknown entity");
+
+    let options = ReadOptions::new().add_entity("product", "ex_em_ell");
+    let example: Example = ex_em_ell::from_reader_with_options(xml.as_bytes(), options)
+        .expect("Failed to parse the XML");
+
+    assert_eq!(example.field, "ex_em_ell");
+}
+
+#[test]
+fn test_namespace_check_option() {
+    let xml = r#"<example><field>value</field></example>"#;
+    ex_em_ell::from_str::<Example>(xml).expect_err("Should fail without the namespace");
+
+    let options = ReadOptions::new().check_namespaces(false);
+    let example: Example = ex_em_ell::from_reader_with_options(xml.as_bytes(), options)
+        .expect("Failed to parse the XML");
+
+    assert_eq!(example.field, "value");
+}
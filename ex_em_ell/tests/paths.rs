@@ -0,0 +1,55 @@
+use ex_em_ell::errors::ElementPathSegment;
+
+#[derive(Debug, PartialEq, ex_em_ell::FromXmlDocument)]
+struct Example {
+    children: Vec<ExampleChild>,
+}
+
+#[derive(Debug, PartialEq, ex_em_ell::FromXmlElement, ex_em_ell::NamedXmlElement)]
+#[ex_em_ell(name = "child")]
+struct ExampleChild {
+    field: String,
+    count: u32,
+}
+
+#[test]
+fn test_missing_field_in_repeated_element() {
+    let xml = r#"<example><children>
+        <child><field>value</field><count>1</count></child>
+        <child><field>value</field></child>
+    </children></example>"#;
+    let error = ex_em_ell::from_str::<Example>(xml).expect_err("Should fail without the count");
+
+    insta::assert_snapshot!(error.to_string(), @"Ended element child at /example/children/child[2] (3:9) without data for required field count");
+}
+
+#[test]
+fn test_invalid_value_in_repeated_element() {
+    let xml = r#"<example><children>
+        <child><field>value</field><count>1</count></child>
+        <child><field>value</field><count>2</count></child>
+        <child><field>value</field><count>three</count></child>
+    </children></example>"#;
+    let error = ex_em_ell::from_str::<Example>(xml).expect_err("Should fail to parse the count");
+
+    let path = error.path().expect("Error should have a path");
+    assert_eq!(path.to_string(), "/example/children/child[3]/count");
+    assert_eq!(
+        path.segments[2],
+        ElementPathSegment {
+            name: "child".to_string(),
+            index: 3,
+        }
+    );
+}
+
+#[test]
+fn test_syntax_error_has_path() {
+    let xml = r#"<example><children><child><field>value</fild></child></children></example>"#;
+    let error = ex_em_ell::from_str::<Example>(xml).expect_err("Should fail on the mismatched tag");
+
+    assert_eq!(
+        error.path().map(ToString::to_string),
+        Some("/example/children/child/field".to_string())
+    );
+}
@@ -0,0 +1,55 @@
+use ex_em_ell::errors::TextPosition;
+
+#[derive(Debug, PartialEq, ex_em_ell::FromXmlDocument)]
+struct Example {
+    count: u32,
+    child: ExampleChild,
+}
+
+#[derive(Debug, PartialEq, ex_em_ell::FromXmlElement)]
+struct ExampleChild {
+    field: String,
+}
+
+#[test]
+fn test_missing_field_reports_parent_start_tag() {
+    let xml = "<example>\n  <count>1</count>\n  <child>\n    <other/>\n  </child>\n</example>";
+    let error = ex_em_ell::from_str::<Example>(xml).expect_err("Should fail on the other element");
+    assert_eq!(error.position(), Some(TextPosition { line: 4, column: 5 }));
+
+    let xml = "<example>\n  <count>1</count>\n  <child>\n  </child>\n</example>";
+    let error = ex_em_ell::from_str::<Example>(xml).expect_err("Should fail without the field");
+    assert_eq!(error.position(), Some(TextPosition { line: 3, column: 3 }));
+}
+
+#[test]
+fn test_invalid_value_reports_element() {
+    let xml = "<example>\n  <count>one</count>\n  <child>\n    <field>value</field>\n  </child>\n</example>";
+    let error = ex_em_ell::from_str::<Example>(xml).expect_err("Should fail to parse the count");
+
+    insta::assert_snapshot!(error.to_string(), @"Could not parse one as xs:unsignedInt on count at /example/count (2:3)");
+}
+
+#[test]
+fn test_syntax_error_reports_position() {
+    let xml =
+        "<example>\n  <count>1</count>\n  <child>\n    <field>value</feld>\n  </child>\n</example>";
+    let error = ex_em_ell::from_str::<Example>(xml).expect_err("Should fail on the mismatched tag");
+
+    assert_eq!(error.position().map(|position| position.line), Some(4));
+}
+
+#[test]
+#[allow(deprecated)]
+fn test_deprecated_helpers_have_no_position() {
+    use ex_em_ell::{events::XmlEvent, xml_utils};
+
+    let text = xml_utils::inner_text_or_error("field")(XmlEvent::Characters("value".to_string()))
+        .expect("Should read the text");
+    assert_eq!(text, "value");
+
+    let error = xml_utils::inner_text_or_error("field")(XmlEvent::EndDocument)
+        .expect_err("Should fail without text");
+    assert_eq!(error.position(), None);
+    insta::assert_snapshot!(error.to_string(), @"Found the end of the document while reading field");
+}
@@ -0,0 +1,162 @@
+#![cfg(feature = "quick-xml")]
+
+use std::io::BufReader;
+
+use ex_em_ell::backend::{
+    quick_xml::{QuickXmlSink, QuickXmlSource},
+    xml_rs::{XmlRsSink, XmlRsSource},
+    XmlEventSink, XmlEventSource,
+};
+use ex_em_ell::events::{WriteEvent, XmlEvent};
+use ex_em_ell::{ReadOptions, WriteOptions, XmlVersion};
+
+/// Read every event of a document, stopping at the first error
+fn read_events(mut source: impl XmlEventSource) -> Result<Vec<XmlEvent>, String> {
+    let mut events = Vec::new();
+    loop {
+        match source.next_event() {
+            Ok(XmlEvent::EndDocument) => return Ok(events),
+            // xml-rs reads a `/` in the data of an instruction as `</`, so only the names are compared
+            Ok(XmlEvent::ProcessingInstruction { name, .. }) => {
+                events.push(XmlEvent::ProcessingInstruction { name, data: None })
+            }
+            Ok(event) => events.push(event),
+            Err(error) => return Err(format!("{error:?}")),
+        }
+    }
+}
+
+fn write_events<S: XmlEventSink>(mut sink: S) -> S::Inner {
+    let events = [
+        WriteEvent::StartDocument {
+            version: XmlVersion::Version10,
+            encoding: "utf-8",
+            standalone: Some(true),
+        },
+        WriteEvent::processing_instruction("xml-stylesheet", Some("href=\"style.xsl\"")),
+        WriteEvent::comment("Generated"),
+        WriteEvent::start_element("example")
+            .default_ns("urn:example")
+            .ns("ext", "urn:extension")
+            .attr("version", "1 < 2 & \"3\"\nline")
+            .into(),
+        WriteEvent::start_element("ext:field")
+            .ns("ext", "urn:extension")
+            .into(),
+        WriteEvent::characters("a < b & c > d"),
+        WriteEvent::end_element(),
+        WriteEvent::start_element("empty").into(),
+        WriteEvent::end_element(),
+        WriteEvent::start_element("children").into(),
+        WriteEvent::start_element("child").into(),
+        WriteEvent::cdata("raw <text>"),
+        WriteEvent::end_element(),
+        WriteEvent::comment(" within "),
+        WriteEvent::end_element(),
+        WriteEvent::end_element(),
+    ];
+    for event in events {
+        sink.write_event(event).expect("Should write the event");
+    }
+    sink.into_inner()
+}
+
+#[test]
+fn test_same_events_as_xml_rs() {
+    insta::glob!("data/*/*.xml", |path| {
+        let options = ReadOptions::default();
+        let open = || std::fs::File::open(path).expect("Should open the file");
+
+        let expected = read_events(XmlRsSource::new(open(), &options));
+        let actual = read_events(QuickXmlSource::new(BufReader::new(open()), &options));
+        match (expected, actual) {
+            (Ok(expected), Ok(actual)) => assert_eq!(expected, actual),
+            (Err(_), Err(_)) => {}
+            (expected, actual) => panic!("{expected:?} != {actual:?}"),
+        }
+    });
+}
+
+#[test]
+fn test_same_output_as_xml_rs() {
+    let options = [
+        WriteOptions::default(),
+        WriteOptions::default().perform_indent(true),
+        WriteOptions::default()
+            .perform_indent(true)
+            .indent_string("\t")
+            .line_separator("\r\n"),
+        WriteOptions::default()
+            .perform_indent(true)
+            .self_closing_empty_elements(false),
+        WriteOptions::default().pad_self_closing(false),
+    ];
+    for options in options {
+        let expected = write_events(XmlRsSink::new(Vec::new(), &options));
+        let actual = write_events(QuickXmlSink::new(Vec::new(), &options));
+        assert_eq!(
+            String::from_utf8(expected).expect("Should be UTF-8"),
+            String::from_utf8(actual).expect("Should be UTF-8"),
+            "{options:?}"
+        );
+    }
+}
+
+#[test]
+fn test_entity_expansion_limit() {
+    let document = r#"<!DOCTYPE example [
+  <!ENTITY a "aaaaaaaaaa">
+  <!ENTITY b "&a;&a;&a;&a;&a;&a;&a;&a;&a;&a;">
+  <!ENTITY c "&b;&b;&b;&b;&b;&b;&b;&b;&b;&b;">
+]>
+<example>&c;</example>"#;
+    let options = ReadOptions::default().doctype(ex_em_ell::DoctypePolicy::AllowInternalEntities {
+        max_expansion_length: 100,
+        max_expansion_depth: 10,
+    });
+
+    let events = read_events(QuickXmlSource::new(document.as_bytes(), &options))
+        .expect_err("Should exceed the expansion budget");
+    insta::assert_snapshot!(events, @"LimitExceeded { limit: EntityExpansion { max_expansion_length: 100, max_expansion_depth: 10 } }");
+}
+
+#[test]
+fn test_same_entities_as_xml_rs() {
+    let document = r#"<!DOCTYPE example [
+  <!-- <!ENTITY comment "ignored"> -->
+  <!ENTITY note "<!ENTITY inner 'not a declaration'>">
+  <!ENTITY a "a">
+  <!ENTITY b "&a;&a;&a;&a;&a;&a;&a;&a;&a;&a;&a;&a;&a;&a;&a;&a;&a;&a;&a;&a;">
+]>
+<example>&b;</example>"#;
+    let options =
+        ReadOptions::default().doctype(ex_em_ell::DoctypePolicy::allow_internal_entities());
+
+    let events = read_events(XmlRsSource::new(document.as_bytes(), &options))
+        .expect("Should expand the entities with xml-rs");
+    assert_eq!(
+        read_events(QuickXmlSource::new(document.as_bytes(), &options)),
+        Ok(events)
+    );
+}
+
+#[test]
+fn test_same_expansion_budget_as_xml_rs() {
+    let options = ReadOptions::default().doctype(ex_em_ell::DoctypePolicy::AllowInternalEntities {
+        max_expansion_length: 1_000,
+        max_expansion_depth: 10,
+    });
+    for references in [100, 101] {
+        let document = format!(
+            r#"<!DOCTYPE example [<!ENTITY e "éééééééééé">]><example a="&e;">{}<![CDATA[&e;]]><!--&e;--></example>"#,
+            "&e;".repeat(references - 1)
+        );
+
+        let events = read_events(XmlRsSource::new(document.as_bytes(), &options));
+        assert_eq!(events.is_ok(), references == 100, "{events:?}");
+        assert_eq!(
+            read_events(QuickXmlSource::new(document.as_bytes(), &options)).is_ok(),
+            events.is_ok(),
+        );
+    }
+}
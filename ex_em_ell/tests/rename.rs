@@ -1,3 +1,8 @@
+// The example files are named in the `expect` messages of the snapshot tests
+#![allow(clippy::expect_fun_call)]
+
+mod common;
+
 // Support Acronym Case conventions without forcing the Rust variable to be field_u_r_l
 #[derive(Debug, PartialEq, ex_em_ell::FromXmlDocument, ex_em_ell::ToXmlDocument)]
 #[ex_em_ell(rename = "exampleURL")]
@@ -26,3 +31,5 @@ fn test_example_xmls() {
         insta::assert_snapshot!(round_trip);
     });
 }
+
+common::test_example_xmls_quick_xml!(Example, "data/rename/rename_*.xml");
@@ -1,3 +1,8 @@
+// The example files are named in the `expect` messages of the snapshot tests
+#![allow(clippy::expect_fun_call)]
+
+mod common;
+
 #[derive(Debug, PartialEq, ex_em_ell::FromXmlDocument, ex_em_ell::ToXmlDocument)]
 struct Example {
     field: String,
@@ -20,3 +25,24 @@ fn test_example_xmls() {
         insta::assert_snapshot!(round_trip);
     });
 }
+
+common::test_example_xmls_quick_xml!(Example, "data/simple/valid_*.xml");
+
+#[test]
+fn test_writer_matches_string() {
+    let example = Example {
+        field: "value".to_string(),
+        child: ExampleChild {
+            field: "child value".to_string(),
+        },
+    };
+
+    let mut output = Vec::new();
+    ex_em_ell::to_writer_pretty(&mut output, &example).expect("Failed to write XML");
+
+    let expected = ex_em_ell::to_string_pretty(&example).expect("Failed to output XML");
+    assert_eq!(
+        String::from_utf8(output).expect("Output should be UTF-8"),
+        expected
+    );
+}
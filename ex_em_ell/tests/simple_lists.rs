@@ -0,0 +1,33 @@
+#[derive(Debug, PartialEq, ex_em_ell::FromXmlDocument, ex_em_ell::ToXmlDocument)]
+struct Example {
+    #[ex_em_ell(attribute, list)]
+    types: Vec<String>,
+
+    #[ex_em_ell(list)]
+    roles: Vec<String>,
+
+    #[ex_em_ell(list)]
+    sizes: Vec<u32>,
+}
+
+#[test]
+fn test_example_xmls() {
+    insta::glob!("data/simple_lists/valid_*.xml", |path| {
+        let file =
+            std::fs::File::open(path).unwrap_or_else(|_| panic!("Failed to read file: {path:?}"));
+        let example: Example = ex_em_ell::from_reader(&file)
+            .unwrap_or_else(|_| panic!("Failed to parse the XML file: {path:?}"));
+
+        let round_trip = ex_em_ell::to_string_pretty(&example).expect("Failed to output XML");
+        insta::assert_snapshot!(round_trip);
+    });
+}
+
+#[test]
+fn test_invalid_list_item() {
+    let xml = r#"<example types="a"><roles>admin</roles><sizes>1 two 3</sizes></example>"#;
+    let error = ex_em_ell::from_reader::<Example, _>(xml.as_bytes())
+        .expect_err("Should fail to parse the second size");
+
+    insta::assert_snapshot!(error.to_string(), @"Could not parse two as xs:unsignedInt on item 2 of sizes at /example/sizes (1:40)");
+}
@@ -0,0 +1,107 @@
+use ex_em_ell::XmlEventReader;
+
+#[derive(Debug, PartialEq, ex_em_ell::FromXmlElement)]
+struct Component {
+    #[ex_em_ell(attribute)]
+    version: u32,
+    name: String,
+}
+
+#[derive(Debug, PartialEq, ex_em_ell::FromXmlElement)]
+struct Metadata {
+    author: String,
+}
+
+const DOCUMENT: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<bom>
+  <metadata><author>Example</author></metadata>
+  <components>
+    <component version="1"><name>first</name></component>
+    <!-- a comment between components -->
+    <component version="two"><name>second</name></component>
+    <component version="3"><name>third</name></component>
+  </components>
+  <dependencies><dependency>first</dependency></dependencies>
+  <serialNumber>12345</serialNumber>
+</bom>"#;
+
+#[test]
+fn test_stream_components() {
+    let mut reader = XmlEventReader::new(DOCUMENT.as_bytes());
+    let components: Vec<_> = reader
+        .stream_elements::<Component>("/bom/components", "component")
+        .expect("Should find the components")
+        .collect();
+
+    assert_eq!(components.len(), 3);
+    assert_eq!(
+        components[0]
+            .as_ref()
+            .expect("Should read the first component"),
+        &Component {
+            version: 1,
+            name: "first".to_string(),
+        }
+    );
+    insta::assert_snapshot!(components[1].as_ref().expect_err("Should fail on the version").to_string(), @"Could not parse two as xs:unsignedInt on component/@version at /bom/components/component[2] (7:5)");
+    assert_eq!(
+        components[2]
+            .as_ref()
+            .expect("Should read the third component"),
+        &Component {
+            version: 3,
+            name: "third".to_string(),
+        }
+    );
+}
+
+#[test]
+fn test_read_fields_after_stream() {
+    let mut reader = XmlEventReader::new(DOCUMENT.as_bytes());
+    let metadata: Option<Metadata> = reader
+        .read_element("/bom/metadata")
+        .expect("Should read the metadata");
+    let names: Vec<String> = reader
+        .stream_elements::<Component>("/bom/components", "component")
+        .expect("Should find the components")
+        .filter_map(Result::ok)
+        .map(|component| component.name)
+        .collect();
+    let serial_number: Option<u64> = reader
+        .read_element("/bom/serialNumber")
+        .expect("Should read the serial number");
+
+    assert_eq!(
+        metadata.map(|metadata| metadata.author).as_deref(),
+        Some("Example")
+    );
+    assert_eq!(names, ["first", "third"]);
+    assert_eq!(serial_number, Some(12345));
+}
+
+#[test]
+fn test_missing_element() {
+    let mut reader = XmlEventReader::new(DOCUMENT.as_bytes());
+    let mut components = reader
+        .stream_elements::<Component>("/bom/services", "service")
+        .expect("Should read to the end of the document");
+
+    assert!(components.next().is_none());
+}
+
+#[test]
+fn test_syntax_error_ends_stream() {
+    let xml = r#"<bom><components><component version="1"><name>first</name></component><component></bom>"#;
+    let mut reader = XmlEventReader::new(xml.as_bytes());
+    let components: Vec<_> = reader
+        .stream_elements::<Component>("/bom/components", "component")
+        .expect("Should find the components")
+        .collect();
+
+    assert_eq!(components.len(), 2);
+    assert!(components[0].is_ok());
+    assert!(!components[1]
+        .as_ref()
+        .expect_err("Should fail on the mismatched tag")
+        .is_recoverable());
+}
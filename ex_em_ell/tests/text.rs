@@ -0,0 +1,68 @@
+#[derive(Debug, PartialEq, ex_em_ell::FromXmlDocument, ex_em_ell::ToXmlDocument)]
+struct Example {
+    field: String,
+    child: ExampleChild,
+}
+
+#[derive(Debug, PartialEq, ex_em_ell::FromXmlElement, ex_em_ell::ToXmlElement)]
+struct ExampleChild {
+    field: String,
+}
+
+#[test]
+fn test_example_xmls() {
+    insta::glob!("data/text/valid_*.xml", |path| {
+        let file =
+            std::fs::File::open(path).unwrap_or_else(|_| panic!("Failed to read file: {path:?}"));
+        let example: Example = ex_em_ell::from_reader(&file)
+            .unwrap_or_else(|_| panic!("Failed to parse the XML file: {path:?}"));
+
+        let round_trip = ex_em_ell::to_string_pretty(&example).expect("Failed to output XML");
+        insta::assert_snapshot!(round_trip);
+    });
+}
+
+/// Read the text of `<field>` in a document whose child field is `child`
+fn read_field(field: &str, options: ex_em_ell::ReadOptions) -> String {
+    let xml =
+        format!("<example><field>{field}</field><child><field>child</field></child></example>");
+    let example: Example = ex_em_ell::from_reader_with_options(xml.as_bytes(), options)
+        .expect("Should read the example");
+    example.field
+}
+
+#[test]
+fn test_whitespace_between_split_text() {
+    let options = ex_em_ell::ReadOptions::default;
+
+    assert_eq!(read_field("x <!--c--> y", options()), "x  y");
+    assert_eq!(read_field("x <![CDATA[y]]> z", options()), "x y z");
+    assert_eq!(read_field("<![CDATA[ x ]]> y", options()), "x  y");
+    assert_eq!(read_field("\n  x <?pi?>\n  y\n", options()), "x \n  y");
+}
+
+#[test]
+fn test_whitespace_kept_without_trimming() {
+    let options = || ex_em_ell::ReadOptions::default().trim_whitespace(false);
+
+    assert_eq!(read_field(" x <!--c--> y ", options()), " x  y ");
+    assert_eq!(read_field(" <![CDATA[ x ]]> ", options()), "  x  ");
+}
+
+#[cfg(feature = "quick-xml")]
+#[test]
+fn test_borrowed_whitespace_between_split_text() {
+    #[derive(Debug, ex_em_ell::FromXmlDocumentBorrowed)]
+    struct Example<'a> {
+        field: std::borrow::Cow<'a, str>,
+    }
+
+    let example: Example =
+        ex_em_ell::from_str_borrowed("<example><field> x <!--c--> y </field></example>")
+            .expect("Should read the example");
+    assert_eq!(example.field, "x  y");
+
+    let example: Example = ex_em_ell::from_str_borrowed("<example><field> x </field></example>")
+        .expect("Should read the example");
+    assert!(matches!(example.field, std::borrow::Cow::Borrowed("x")));
+}
@@ -0,0 +1,57 @@
+use ex_em_ell::errors::{XmlEventKind, XmlReadError};
+
+#[derive(Debug, PartialEq, ex_em_ell::FromXmlDocument)]
+struct Example {
+    field: String,
+    child: ExampleChild,
+}
+
+#[derive(Debug, PartialEq, ex_em_ell::FromXmlElement)]
+struct ExampleChild {
+    count: u32,
+}
+
+#[test]
+fn test_unexpected_start_element() {
+    let xml = r#"<example><field>value</field><other /><child><count>1</count></child></example>"#;
+    let error = ex_em_ell::from_str::<Example>(xml).expect_err("Should fail on the other element");
+
+    match &error {
+        XmlReadError::UnexpectedElementReadError {
+            found,
+            found_name,
+            expected,
+            ..
+        } => {
+            assert_eq!(*found, XmlEventKind::StartElement);
+            assert_eq!(found_name.as_deref(), Some("other"));
+            assert_eq!(expected.as_ref(), ["field", "child"]);
+        }
+        other => panic!("Expected an unexpected element error, got {other:?}"),
+    }
+    insta::assert_snapshot!(error.to_string(), @"Found `<other>` while reading example, expected one of `<field>`, `<child>` at /example/other (1:30)");
+}
+
+#[test]
+fn test_unexpected_text() {
+    let xml = r#"<example><field>value</field><child>text<count>1</count></child></example>"#;
+    let error = ex_em_ell::from_str::<Example>(xml).expect_err("Should fail on the text");
+
+    insta::assert_snapshot!(error.to_string(), @"Found text while reading child, expected `<count>` at /example/child (1:37)");
+}
+
+#[test]
+fn test_unexpected_root_element() {
+    let xml = r#"<other><field>value</field></other>"#;
+    let error = ex_em_ell::from_str::<Example>(xml).expect_err("Should fail on the root element");
+
+    insta::assert_snapshot!(error.to_string(), @"Found `<other>` while reading example, expected `<example>` at /other (1:7)");
+}
+
+#[test]
+fn test_element_inside_simple_element() {
+    let xml = r#"<example><field><b>value</b></field><child><count>1</count></child></example>"#;
+    let error = ex_em_ell::from_str::<Example>(xml).expect_err("Should fail on the nested element");
+
+    insta::assert_snapshot!(error.to_string(), @"Found `<b>` while reading field at /example/field/b (1:17)");
+}
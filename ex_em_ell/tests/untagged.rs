@@ -0,0 +1,87 @@
+#[derive(Debug, PartialEq, ex_em_ell::FromXmlDocument, ex_em_ell::ToXmlDocument)]
+struct Library {
+    books: Vec<Book>,
+}
+
+#[derive(
+    Debug, PartialEq, ex_em_ell::FromXmlElement, ex_em_ell::ToXmlElement, ex_em_ell::NamedXmlElement,
+)]
+struct Book {
+    title: String,
+    author: Author,
+}
+
+#[derive(Debug, PartialEq, ex_em_ell::FromXmlElement, ex_em_ell::ToXmlElement)]
+#[ex_em_ell(untagged)]
+enum Author {
+    Person(Person),
+    Name(String),
+}
+
+#[derive(Debug, PartialEq, ex_em_ell::FromXmlElement, ex_em_ell::ToXmlElement)]
+struct Person {
+    name: String,
+    email: String,
+}
+
+#[test]
+fn test_example_xmls() {
+    insta::glob!("data/untagged/valid_*.xml", |path| {
+        let file =
+            std::fs::File::open(path).unwrap_or_else(|_| panic!("Failed to read file: {path:?}"));
+        let library: Library = ex_em_ell::from_reader(&file)
+            .unwrap_or_else(|_| panic!("Failed to parse the XML file: {path:?}"));
+
+        let round_trip = ex_em_ell::to_string_pretty(&library).expect("Failed to output XML");
+        insta::assert_snapshot!(round_trip);
+    });
+}
+
+#[test]
+fn test_first_matching_variant() {
+    let xml = r#"<library><books><book><title>Emma</title><author><name>Jane Austen</name><email>jane@example.com</email></author></book><book><title>Persuasion</title><author>Jane Austen</author></book></books></library>"#;
+    let library: Library = ex_em_ell::from_str(xml).expect("Should read both authors");
+
+    assert_eq!(
+        library.books[0].author,
+        Author::Person(Person {
+            name: "Jane Austen".to_string(),
+            email: "jane@example.com".to_string(),
+        })
+    );
+    assert_eq!(
+        library.books[1].author,
+        Author::Name("Jane Austen".to_string())
+    );
+}
+
+#[test]
+fn test_no_matching_variant() {
+    let xml = r#"<library><books><book><title>Emma</title><author><name>Jane Austen</name></author></book></books></library>"#;
+    let error =
+        ex_em_ell::from_str::<Library>(xml).expect_err("Should fail to read the author as either");
+
+    insta::assert_snapshot!(error.to_string(), @"None of the variants of author could be read at /library/books/book/author (1:42): Person failed with \"Ended element author at /library/books/book/author (1:42) without data for required field email\", Name failed with \"Found `<name>` while reading author at /library/books/book/author/name (1:50)\"");
+}
+
+#[test]
+fn test_collect_errors_after_no_matching_variant() {
+    let xml = r#"<library><books><book><title>Emma</title><author><name>Jane Austen</name></author></book><book><title>Persuasion</title><author>Jane Austen</author></book><book><title>Lady Susan</title><author><email>jane@example.com</email></author></book></books></library>"#;
+    let errors = ex_em_ell::from_reader_collect_errors::<Library, _>(xml.as_bytes())
+        .expect_err("Should fail to read two of the authors");
+
+    let errors: Vec<_> = errors.iter().map(ToString::to_string).collect();
+    insta::assert_snapshot!(errors.join("\n"), @r###"
+    None of the variants of author could be read at /library/books/book/author (1:42): Person failed with "Ended element author at /library/books/book/author (1:42) without data for required field email", Name failed with "Found `<name>` while reading author at /library/books/book/author/name (1:50)"
+    None of the variants of author could be read at /library/books/book[3]/author (1:187): Person failed with "Ended element author at /library/books/book[3]/author (1:187) without data for required field name", Name failed with "Found `<email>` while reading author at /library/books/book[3]/author/email (1:195)"
+    "###);
+}
+
+#[test]
+fn test_syntax_error_in_variant() {
+    let xml = r#"<library><books><book><title>Emma</title><author><name>Jane Austen</nam></author></book></books></library>"#;
+    let error = ex_em_ell::from_str::<Library>(xml).expect_err("Should fail on the syntax error");
+
+    // The syntax error is reported as soon as the first variant finds it
+    insta::assert_snapshot!(error.to_string(), @"Failed to deserialize XML while reading name at /library/books/book/author/name (1:72): 1:72 Unexpected closing tag: nam != name");
+}
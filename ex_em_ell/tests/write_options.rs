@@ -0,0 +1,136 @@
+use ex_em_ell::{WriteOptions, XmlVersion};
+
+#[derive(Debug, PartialEq, ex_em_ell::ToXmlDocument)]
+struct Example {
+    field: String,
+    empty: String,
+    marker: Marker,
+    child: ExampleChild,
+}
+
+#[derive(Debug, PartialEq, ex_em_ell::ToXmlElement)]
+struct Marker {}
+
+#[derive(Debug, PartialEq, ex_em_ell::ToXmlElement)]
+struct ExampleChild {
+    field: String,
+}
+
+fn example() -> Example {
+    Example {
+        field: "value".to_string(),
+        empty: String::new(),
+        marker: Marker {},
+        child: ExampleChild {
+            field: "value".to_string(),
+        },
+    }
+}
+
+#[test]
+fn test_default_options() {
+    let output = ex_em_ell::to_string_with_options(&example(), WriteOptions::new())
+        .expect("Failed to output XML");
+
+    insta::assert_snapshot!(output, @r#"<?xml version="1.0" encoding="utf-8"?><example><field>value</field><empty></empty><marker /><child><field>value</field></child></example>"#);
+}
+
+#[test]
+fn test_without_declaration() {
+    let options = WriteOptions::new().write_document_declaration(false);
+    let output =
+        ex_em_ell::to_string_with_options(&example(), options).expect("Failed to output XML");
+
+    insta::assert_snapshot!(output, @"<example><field>value</field><empty></empty><marker /><child><field>value</field></child></example>");
+}
+
+#[test]
+fn test_declaration_contents() {
+    let options = WriteOptions::new()
+        .xml_version(XmlVersion::Version11)
+        .encoding("UTF-8")
+        .standalone(Some(true));
+    let output =
+        ex_em_ell::to_string_with_options(&example(), options).expect("Failed to output XML");
+
+    insta::assert_snapshot!(output, @r#"<?xml version="1.1" encoding="UTF-8" standalone="yes"?><example><field>value</field><empty></empty><marker /><child><field>value</field></child></example>"#);
+}
+
+#[test]
+fn test_indentation() {
+    let options = WriteOptions::new()
+        .perform_indent(true)
+        .indent_string("\t")
+        .line_separator("\r\n");
+    let output =
+        ex_em_ell::to_string_with_options(&example(), options).expect("Failed to output XML");
+
+    assert_eq!(
+        output,
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\r\n<example>\r\n\t<field>value</field>\r\n\t<empty></empty>\r\n\t<marker />\r\n\t<child>\r\n\t\t<field>value</field>\r\n\t</child>\r\n</example>"
+    );
+}
+
+#[test]
+fn test_empty_element_style() {
+    let options = WriteOptions::new()
+        .write_document_declaration(false)
+        .pad_self_closing(false);
+    let output =
+        ex_em_ell::to_string_with_options(&example(), options).expect("Failed to output XML");
+    insta::assert_snapshot!(output, @"<example><field>value</field><empty></empty><marker/><child><field>value</field></child></example>");
+
+    let options = WriteOptions::new()
+        .write_document_declaration(false)
+        .self_closing_empty_elements(false);
+    let output =
+        ex_em_ell::to_string_with_options(&example(), options).expect("Failed to output XML");
+    insta::assert_snapshot!(output, @"<example><field>value</field><empty></empty><marker></marker><child><field>value</field></child></example>");
+}
+
+#[derive(Debug, PartialEq, ex_em_ell::FromXmlDocument, ex_em_ell::ToXmlDocument)]
+struct Note {
+    text: String,
+}
+
+#[test]
+fn test_ascii_compatible_encoding() {
+    let note = Note {
+        text: "Caf\u{e9}".to_string(),
+    };
+    let options = WriteOptions::new().encoding("ISO-8859-1");
+
+    // Documents are written as UTF-8, so text that ISO-8859-1 would write differently is rejected
+    let error = ex_em_ell::to_string_with_options(&note, options.clone())
+        .expect_err("Should not write text that is not ASCII as ISO-8859-1");
+    insta::assert_snapshot!(error.to_string(), @r###"Cannot write "Café" in text, as documents are written as UTF-8 and only ASCII is written the same in the declared encoding ISO-8859-1"###);
+
+    let note = Note {
+        text: "Cafe & more".to_string(),
+    };
+    let output = ex_em_ell::to_string_with_options(&note, options).expect("Failed to output XML");
+    insta::assert_snapshot!(output, @r###"<?xml version="1.0" encoding="ISO-8859-1"?><note><text>Cafe &amp; more</text></note>"###);
+    let read: Note = ex_em_ell::from_str(&output).expect("Should read the ISO-8859-1 document");
+    assert_eq!(note, read);
+}
+
+#[test]
+fn test_utf8_encoding_round_trip() {
+    let note = Note {
+        text: "Caf\u{e9} \u{2615}".to_string(),
+    };
+    let options = WriteOptions::new().encoding("UTF8");
+    let output = ex_em_ell::to_string_with_options(&note, options).expect("Failed to output XML");
+
+    let read: Note = ex_em_ell::from_str(&output).expect("Should read the UTF-8 document");
+    assert_eq!(note, read);
+}
+
+#[test]
+fn test_unsupported_encoding() {
+    let options = WriteOptions::new().encoding("UTF-16");
+    let error = ex_em_ell::to_string_with_options(&example(), options)
+        .expect_err("Should not declare an encoding that ASCII is written differently in");
+
+    insta::assert_snapshot!(error.to_string(), @"Cannot declare the encoding UTF-16, as documents are written as UTF-8 and it does not write ASCII the same way");
+}
@@ -0,0 +1,186 @@
+#[derive(Debug, PartialEq, ex_em_ell::FromXmlDocument, ex_em_ell::ToXmlDocument)]
+struct Drawing {
+    shapes: Vec<Shape>,
+}
+
+#[derive(
+    Debug, PartialEq, ex_em_ell::FromXmlElement, ex_em_ell::ToXmlElement, ex_em_ell::NamedXmlElement,
+)]
+#[ex_em_ell(xsi_type)]
+enum Shape {
+    #[ex_em_ell(
+        rename = "CircleType",
+        namespace = "urn:example:shapes",
+        prefix = "shp"
+    )]
+    Circle(Circle),
+    #[ex_em_ell(
+        rename = "RectangleType",
+        namespace = "urn:example:shapes",
+        prefix = "shp"
+    )]
+    Rectangle(Rectangle),
+    Square(Square),
+}
+
+#[derive(Debug, PartialEq, ex_em_ell::FromXmlElement, ex_em_ell::ToXmlElement)]
+struct Circle {
+    radius: f64,
+}
+
+#[derive(Debug, PartialEq, ex_em_ell::FromXmlElement, ex_em_ell::ToXmlElement)]
+struct Rectangle {
+    width: f64,
+    height: f64,
+}
+
+#[derive(Debug, PartialEq, ex_em_ell::FromXmlElement, ex_em_ell::ToXmlElement)]
+struct Square {
+    side: f64,
+}
+
+#[test]
+fn test_example_xmls() {
+    insta::glob!("data/xsi_type/valid_*.xml", |path| {
+        let file =
+            std::fs::File::open(path).unwrap_or_else(|_| panic!("Failed to read file: {path:?}"));
+        let drawing: Drawing = ex_em_ell::from_reader(&file)
+            .unwrap_or_else(|_| panic!("Failed to parse the XML file: {path:?}"));
+
+        let round_trip = ex_em_ell::to_string_pretty(&drawing).expect("Failed to output XML");
+        insta::assert_snapshot!(round_trip);
+    });
+}
+
+#[test]
+fn test_dispatch_on_resolved_namespace() {
+    let xml = r#"<drawing xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xmlns:a="urn:example:shapes"><shapes><shape xmlns:b="urn:example:shapes" xsi:type="b:CircleType"><radius>1</radius></shape><shape xsi:type="a:RectangleType"><width>2</width><height>3</height></shape></shapes></drawing>"#;
+    let drawing: Drawing = ex_em_ell::from_str(xml).expect("Should read both shapes");
+
+    assert_eq!(
+        drawing.shapes,
+        vec![
+            Shape::Circle(Circle { radius: 1.0 }),
+            Shape::Rectangle(Rectangle {
+                width: 2.0,
+                height: 3.0
+            }),
+        ]
+    );
+}
+
+#[test]
+fn test_write_declares_namespaces() {
+    let drawing = Drawing {
+        shapes: vec![
+            Shape::Circle(Circle { radius: 1.0 }),
+            Shape::Square(Square { side: 2.0 }),
+        ],
+    };
+    let xml = ex_em_ell::to_string(&drawing).expect("Should write the drawing");
+
+    insta::assert_snapshot!(xml, @r###"<?xml version="1.0" encoding="utf-8"?><drawing><shapes><shape xmlns:shp="urn:example:shapes" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xsi:type="shp:CircleType"><radius>1</radius></shape><shape xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xsi:type="Square"><side>2</side></shape></shapes></drawing>"###);
+    assert_eq!(
+        ex_em_ell::from_str::<Drawing>(&xml).expect("Should read the written drawing"),
+        drawing
+    );
+}
+
+#[derive(Debug, PartialEq, ex_em_ell::FromXmlDocument, ex_em_ell::ToXmlDocument)]
+#[ex_em_ell(namespace = "urn:example:drawing")]
+struct NamespacedDrawing {
+    shapes: Vec<Shape>,
+}
+
+#[test]
+fn test_type_without_namespace_in_default_namespace() {
+    let drawing = NamespacedDrawing {
+        shapes: vec![
+            Shape::Square(Square { side: 2.0 }),
+            Shape::Circle(Circle { radius: 1.0 }),
+        ],
+    };
+    let xml = ex_em_ell::to_string(&drawing).expect("Should write the drawing");
+
+    insta::assert_snapshot!(xml, @r###"<?xml version="1.0" encoding="utf-8"?><namespacedDrawing xmlns="urn:example:drawing"><shapes><shape xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xsi:type="Square"><side>2</side></shape><shape xmlns:shp="urn:example:shapes" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xsi:type="shp:CircleType"><radius>1</radius></shape></shapes></namespacedDrawing>"###);
+    assert_eq!(
+        ex_em_ell::from_str::<NamespacedDrawing>(&xml).expect("Should read the written drawing"),
+        drawing
+    );
+}
+
+#[test]
+fn test_unknown_type() {
+    let xml = r#"<drawing xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xmlns:shp="urn:example:shapes"><shapes><shape xsi:type="shp:TriangleType"><base>1</base></shape></shapes></drawing>"#;
+    let error = ex_em_ell::from_str::<Drawing>(xml).expect_err("Should not read an unknown type");
+
+    insta::assert_snapshot!(error.to_string(), @r###"Found xsi:type "shp:TriangleType" on shape, expected one of {urn:example:shapes}CircleType, {urn:example:shapes}RectangleType, Square at /drawing/shapes/shape (1:103)"###);
+}
+
+#[test]
+fn test_type_in_another_namespace() {
+    let xml = r#"<drawing xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xmlns:shp="urn:example:other"><shapes><shape xsi:type="shp:CircleType"><radius>1</radius></shape></shapes></drawing>"#;
+    let error = ex_em_ell::from_str::<Drawing>(xml)
+        .expect_err("Should not read a type with the same name from another namespace");
+
+    insta::assert_snapshot!(error.to_string(), @r###"Found xsi:type "shp:CircleType" on shape, expected one of {urn:example:shapes}CircleType, {urn:example:shapes}RectangleType, Square at /drawing/shapes/shape (1:102)"###);
+}
+
+#[test]
+fn test_undeclared_prefix() {
+    let xml = r#"<drawing xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance"><shapes><shape xsi:type="shp:CircleType"><radius>1</radius></shape></shapes></drawing>"#;
+    let error = ex_em_ell::from_str::<Drawing>(xml).expect_err("Should not resolve the prefix");
+
+    insta::assert_snapshot!(error.to_string(), @"Could not parse shp:CircleType as xs:QName on shape/@xsi:type at /drawing/shapes/shape (1:72)");
+}
+
+#[test]
+fn test_missing_type() {
+    let xml = r#"<drawing><shapes><shape><radius>1</radius></shape></shapes></drawing>"#;
+    let error = ex_em_ell::from_str::<Drawing>(xml).expect_err("Should need an xsi:type");
+
+    insta::assert_snapshot!(error.to_string(), @"Ended element shape at /drawing/shapes/shape (1:18) without data for required field xsi:type");
+}
+
+#[derive(Debug, PartialEq, ex_em_ell::FromXmlDocument, ex_em_ell::ToXmlDocument)]
+struct Figure {
+    shapes: Vec<FigureShape>,
+}
+
+/// A shape that may be a group of other shapes, which are each written with an `xsi:type` of
+/// their own
+#[derive(
+    Debug, PartialEq, ex_em_ell::FromXmlElement, ex_em_ell::ToXmlElement, ex_em_ell::NamedXmlElement,
+)]
+#[ex_em_ell(xsi_type)]
+enum FigureShape {
+    Circle(Circle),
+    Group(Group),
+}
+
+#[derive(Debug, PartialEq, ex_em_ell::FromXmlElement, ex_em_ell::ToXmlElement)]
+struct Group {
+    shapes: Vec<FigureShape>,
+}
+
+#[test]
+fn test_recursive_types() {
+    let figure = Figure {
+        shapes: vec![
+            FigureShape::Circle(Circle { radius: 1.0 }),
+            FigureShape::Group(Group {
+                shapes: vec![
+                    FigureShape::Group(Group {
+                        shapes: vec![FigureShape::Circle(Circle { radius: 2.0 })],
+                    }),
+                    FigureShape::Circle(Circle { radius: 3.0 }),
+                ],
+            }),
+        ],
+    };
+    let xml = ex_em_ell::to_string(&figure).expect("Should write the figure");
+    insta::assert_snapshot!(xml, @r###"<?xml version="1.0" encoding="utf-8"?><figure><shapes><figureShape xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xsi:type="Circle"><radius>1</radius></figureShape><figureShape xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xsi:type="Group"><shapes><figureShape xsi:type="Group"><shapes><figureShape xsi:type="Circle"><radius>2</radius></figureShape></shapes></figureShape><figureShape xsi:type="Circle"><radius>3</radius></figureShape></shapes></figureShape></shapes></figure>"###);
+
+    let read: Figure = ex_em_ell::from_str(&xml).expect("Should read the figure");
+    assert_eq!(figure, read);
+}
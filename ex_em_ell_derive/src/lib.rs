@@ -3,12 +3,14 @@
 use darling::FromMeta;
 use heck::ToLowerCamelCase;
 use quote::{format_ident, quote};
-use syn::{parse_macro_input, parse_quote, DeriveInput, GenericParam, Generics};
+use syn::{
+    parse_macro_input, parse_quote, DeriveInput, GenericParam, Generics, Lifetime, LifetimeParam,
+};
 
 mod read;
 mod write;
 
-use read::{generate_read_xml_document, generate_read_xml_element};
+use read::{generate_read_xml_document, generate_read_xml_element, ReadMode};
 use write::{generate_write_xml_document, generate_write_xml_element};
 
 #[proc_macro_error::proc_macro_error]
@@ -18,7 +20,7 @@ pub fn decode_derive_document(input: proc_macro::TokenStream) -> proc_macro::Tok
 
     let reader_variable = format_ident!("_{}", "reader");
 
-    let read_xml_document = generate_read_xml_document(&input, &reader_variable);
+    let read_xml_document = generate_read_xml_document(&input, &reader_variable, ReadMode::Owned);
 
     let name = input.ident;
 
@@ -28,7 +30,7 @@ pub fn decode_derive_document(input: proc_macro::TokenStream) -> proc_macro::Tok
     let expanded = quote! {
         // The generated impl.
         impl #impl_generics ex_em_ell::traits::FromXmlDocument for #name #ty_generics #where_clause {
-            fn from_xml_document<R: std::io::Read>(#reader_variable: &mut ex_em_ell::xml::EventReader<R>) -> Result<Self, ex_em_ell::errors::XmlReadError>
+            fn from_xml_document<R: ex_em_ell::backend::XmlEventSource>(#reader_variable: &mut ex_em_ell::reader::XmlEventReader<R>) -> Result<Self, ex_em_ell::errors::XmlReadError>
             {
                 #read_xml_document
             }
@@ -47,7 +49,12 @@ pub fn decode_derive_element(input: proc_macro::TokenStream) -> proc_macro::Toke
     let reader_variable = format_ident!("_{}", "reader");
     let tag_name_variable = format_ident!("_{}", "tag_name");
 
-    let read_xml_element = generate_read_xml_element(&input, &reader_variable, &tag_name_variable);
+    let read_xml_element = generate_read_xml_element(
+        &input,
+        &reader_variable,
+        &tag_name_variable,
+        ReadMode::Owned,
+    );
 
     let name = input.ident;
 
@@ -57,7 +64,7 @@ pub fn decode_derive_element(input: proc_macro::TokenStream) -> proc_macro::Toke
     let expanded = quote! {
         // The generated impl.
         impl #impl_generics ex_em_ell::traits::FromXmlElement for #name #ty_generics #where_clause {
-            fn from_xml_element<R: std::io::Read>(#reader_variable: &mut ex_em_ell::xml::EventReader<R>, #tag_name_variable: &ex_em_ell::xml::name::OwnedName, element_attributes: &[ex_em_ell::xml::attribute::OwnedAttribute], element_namespace: &ex_em_ell::xml::namespace::Namespace) -> Result<Self, ex_em_ell::errors::XmlReadError>
+            fn from_xml_element<R: ex_em_ell::backend::XmlEventSource>(#reader_variable: &mut ex_em_ell::reader::XmlEventReader<R>, #tag_name_variable: &ex_em_ell::events::XmlName, element_attributes: &[ex_em_ell::events::XmlAttribute], element_namespace: &ex_em_ell::events::Namespace) -> Result<Self, ex_em_ell::errors::XmlReadError>
             {
                 #read_xml_element
             }
@@ -80,6 +87,93 @@ fn add_from_xml_element_trait_bounds(mut generics: Generics) -> Generics {
     generics
 }
 
+#[proc_macro_error::proc_macro_error]
+#[proc_macro_derive(FromXmlDocumentBorrowed, attributes(ex_em_ell))]
+pub fn decode_derive_document_borrowed(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    let reader_variable = format_ident!("_{}", "reader");
+
+    let read_xml_document =
+        generate_read_xml_document(&input, &reader_variable, ReadMode::Borrowed);
+
+    let name = &input.ident;
+
+    let generics = add_from_xml_element_borrowed_trait_bounds(input.generics.clone());
+    let (impl_generics, _, _) = generics.split_for_impl();
+    let (_, ty_generics, where_clause) = input.generics.split_for_impl();
+    let expanded = quote! {
+        // The generated impl.
+        impl #impl_generics ex_em_ell::traits::FromXmlDocumentBorrowed<'de> for #name #ty_generics #where_clause {
+            fn from_xml_document_borrowed<R: ex_em_ell::backend::XmlBorrowedEventSource<'de>>(#reader_variable: &mut ex_em_ell::reader::XmlEventReader<R>) -> Result<Self, ex_em_ell::errors::XmlReadError>
+            {
+                #read_xml_document
+            }
+        }
+    };
+
+    // Hand the output tokens back to the compiler.
+    proc_macro::TokenStream::from(expanded)
+}
+
+#[proc_macro_error::proc_macro_error]
+#[proc_macro_derive(FromXmlElementBorrowed, attributes(ex_em_ell))]
+pub fn decode_derive_element_borrowed(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    let reader_variable = format_ident!("_{}", "reader");
+    let tag_name_variable = format_ident!("_{}", "tag_name");
+
+    let read_xml_element = generate_read_xml_element(
+        &input,
+        &reader_variable,
+        &tag_name_variable,
+        ReadMode::Borrowed,
+    );
+
+    let name = &input.ident;
+
+    let generics = add_from_xml_element_borrowed_trait_bounds(input.generics.clone());
+    let (impl_generics, _, _) = generics.split_for_impl();
+    let (_, ty_generics, where_clause) = input.generics.split_for_impl();
+    let expanded = quote! {
+        // The generated impl.
+        impl #impl_generics ex_em_ell::traits::FromXmlElementBorrowed<'de> for #name #ty_generics #where_clause {
+            fn from_xml_element_borrowed<R: ex_em_ell::backend::XmlBorrowedEventSource<'de>>(#reader_variable: &mut ex_em_ell::reader::XmlEventReader<R>, #tag_name_variable: &ex_em_ell::events::XmlName, element_attributes: &[ex_em_ell::events::XmlAttribute<std::borrow::Cow<'de, str>>], element_namespace: &ex_em_ell::events::Namespace) -> Result<Self, ex_em_ell::errors::XmlReadError>
+            {
+                #read_xml_element
+            }
+        }
+    };
+
+    // Hand the output tokens back to the compiler.
+    proc_macro::TokenStream::from(expanded)
+}
+
+// Add the lifetime `'de` of the input, which outlives every lifetime of the type, unless the type
+// already borrows for `'de`, and a bound `T: FromXmlElementBorrowed<'de>` to every type parameter T.
+fn add_from_xml_element_borrowed_trait_bounds(mut generics: Generics) -> Generics {
+    let input_lifetime: Lifetime = parse_quote!('de);
+    let lifetimes: Vec<Lifetime> = generics
+        .lifetimes()
+        .map(|param| param.lifetime.clone())
+        .collect();
+    if !lifetimes.contains(&input_lifetime) {
+        let mut param = LifetimeParam::new(input_lifetime);
+        param.bounds.extend(lifetimes);
+        generics.params.insert(0, GenericParam::Lifetime(param));
+    }
+
+    for param in &mut generics.params {
+        if let GenericParam::Type(ref mut type_param) = *param {
+            type_param
+                .bounds
+                .push(parse_quote!(ex_em_ell::FromXmlElementBorrowed<'de>));
+        }
+    }
+    generics
+}
+
 #[proc_macro_error::proc_macro_error]
 #[proc_macro_derive(ToXmlDocument, attributes(ex_em_ell))]
 pub fn enecode_derive_document(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
@@ -96,7 +190,7 @@ pub fn enecode_derive_document(input: proc_macro::TokenStream) -> proc_macro::To
     let expanded = quote! {
         // The generated impl.
         impl #impl_generics ex_em_ell::traits::ToXmlDocument for #name #ty_generics #where_clause {
-            fn to_xml_document<W: std::io::Write>(self: &Self, #writer_variable: &mut ex_em_ell::xml::EventWriter<W>) -> Result<(), ex_em_ell::errors::XmlWriteError>
+            fn to_xml_document<W: ex_em_ell::backend::XmlEventSink>(self: &Self, #writer_variable: &mut W) -> Result<(), ex_em_ell::errors::XmlWriteError>
             {
                 #write_xml_document
 
@@ -118,7 +212,7 @@ pub fn enecode_derive_element(input: proc_macro::TokenStream) -> proc_macro::Tok
     let tag_name_variable = format_ident!("_{}", "tag_name");
 
     let write_xml_element =
-        generate_write_xml_element(&input, &writer_variable, &tag_name_variable);
+        generate_write_xml_element(&input, &writer_variable, &tag_name_variable, None);
 
     let name = input.ident;
 
@@ -128,7 +222,7 @@ pub fn enecode_derive_element(input: proc_macro::TokenStream) -> proc_macro::Tok
     let expanded = quote! {
         // The generated impl.
         impl #impl_generics ex_em_ell::traits::ToXmlElement for #name #ty_generics #where_clause {
-            fn to_xml_element<W: std::io::Write>(self: &Self, #writer_variable: &mut ex_em_ell::xml::EventWriter<W>, #tag_name_variable: &str) -> Result<(), ex_em_ell::errors::XmlWriteError>
+            fn to_xml_element<W: ex_em_ell::backend::XmlEventSink>(self: &Self, #writer_variable: &mut W, #tag_name_variable: &str) -> Result<(), ex_em_ell::errors::XmlWriteError>
             {
                 #write_xml_element
 
@@ -174,6 +268,52 @@ pub fn name_derive_element(input: proc_macro::TokenStream) -> proc_macro::TokenS
     proc_macro::TokenStream::from(expanded)
 }
 
+/// Whether `element_form_default` makes the child elements of a type unqualified, as under
+/// `elementFormDefault="unqualified"` in an XML Schema
+pub(crate) fn is_unqualified_form(
+    element_form_default: Option<&str>,
+    span: proc_macro2::Span,
+) -> bool {
+    match element_form_default {
+        None | Some("qualified") => false,
+        Some("unqualified") => true,
+        Some(_) => proc_macro_error::abort!(
+            span,
+            "`element_form_default` must be \"qualified\" or \"unqualified\""
+        ),
+    }
+}
+
+/// Fail on a `namespace` on a field, which is only supported on types and `xsi_type` variants
+pub(crate) fn reject_field_namespace(namespace: Option<&str>, field: &syn::Field) {
+    if namespace.is_some() {
+        proc_macro_error::abort!(
+            field,
+            "`namespace` is not supported on fields, child elements are in the namespace of their parent unless the type has `element_form_default = \"unqualified\"`"
+        );
+    }
+}
+
+/// The type `T` of a field declared as `Option<T>`
+pub(crate) fn option_inner_type(ty: &syn::Type) -> Option<&syn::Type> {
+    let syn::Type::Path(type_path) = ty else {
+        return None;
+    };
+    let segment = type_path.path.segments.last()?;
+    if segment.ident != "Option" {
+        return None;
+    }
+    match &segment.arguments {
+        syn::PathArguments::AngleBracketed(arguments) if arguments.args.len() == 1 => {
+            match arguments.args.first()? {
+                syn::GenericArgument::Type(inner) => Some(inner),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
 #[derive(Debug, Default, FromMeta)]
 struct NameAttrs {
     #[darling(default)]
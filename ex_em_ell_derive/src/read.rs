@@ -3,11 +3,80 @@ use heck::ToLowerCamelCase;
 use proc_macro2::{Ident, TokenStream};
 use quote::{format_ident, quote, quote_spanned};
 use syn::spanned::Spanned;
-use syn::{Data, DeriveInput, Fields};
+use syn::{Data, DataEnum, DeriveInput, Fields};
+
+/// Whether the generated code reads owned events, or events that borrow their text from the input
+#[derive(Clone, Copy)]
+pub(crate) enum ReadMode {
+    Owned,
+    Borrowed,
+}
+
+impl ReadMode {
+    fn next_event(self) -> TokenStream {
+        match self {
+            ReadMode::Owned => quote! { next_event },
+            ReadMode::Borrowed => quote! { next_borrowed_event },
+        }
+    }
+
+    fn next_non_misc_event(self) -> TokenStream {
+        match self {
+            ReadMode::Owned => quote! { ex_em_ell::xml_utils::next_non_misc_event },
+            ReadMode::Borrowed => quote! { ex_em_ell::xml_utils::next_non_misc_borrowed_event },
+        }
+    }
+
+    fn attributes_type(self) -> TokenStream {
+        match self {
+            ReadMode::Owned => quote! { ex_em_ell::events::XmlAttribute },
+            ReadMode::Borrowed => {
+                quote! { ex_em_ell::events::XmlAttribute<std::borrow::Cow<'_, str>> }
+            }
+        }
+    }
+
+    fn read_attribute(self) -> TokenStream {
+        match self {
+            ReadMode::Owned => quote! { ex_em_ell::xml_utils::read_attribute },
+            ReadMode::Borrowed => quote! { ex_em_ell::xml_utils::read_attribute_borrowed },
+        }
+    }
+
+    fn read_element(self) -> TokenStream {
+        match self {
+            ReadMode::Owned => quote! { ex_em_ell::traits::FromXmlElement::from_xml_element },
+            ReadMode::Borrowed => quote! {
+                ex_em_ell::traits::FromXmlElementBorrowed::from_xml_element_borrowed
+            },
+        }
+    }
+
+    /// The value of a field of `element_type` whose element is missing, if it can be missing
+    fn missing_element(self, element_type: &syn::Type) -> TokenStream {
+        match self {
+            ReadMode::Owned => quote! {
+                <#element_type as ex_em_ell::traits::FromXmlElement>::from_missing_element()
+            },
+            ReadMode::Borrowed => quote! {
+                <#element_type as ex_em_ell::traits::FromXmlElementBorrowed<'_>>::from_missing_element()
+            },
+        }
+    }
+
+    fn comment(self) -> TokenStream {
+        match self {
+            ReadMode::Owned => quote! { comment },
+            // Comments may be kept as borrowed or owned strings
+            ReadMode::Borrowed => quote! { Into::into(comment) },
+        }
+    }
+}
 
 pub(crate) fn generate_read_xml_document(
     input: &DeriveInput,
     reader_variable: &Ident,
+    mode: ReadMode,
 ) -> TokenStream {
     let read_attrs: ReadAttrs = input
         .attrs
@@ -18,57 +87,74 @@ pub(crate) fn generate_read_xml_document(
         .rename
         .unwrap_or_else(|| input.ident.to_string().to_lower_camel_case());
 
-    // Borrow the tag OwnedName so it can be consistent with what's passed to FromXmlElement
+    // Borrow the tag XmlName so it can be consistent with what's passed to FromXmlElement
     let tag_name_variable = format_ident!("_{}", "tag_name");
     let tag_name_borrowed_variable = format_ident!("_{}_borrowed", tag_name_variable);
+    let attributes_variable = format_ident!("_{}", "attributes");
+    let namespace_variable = format_ident!("_{}", "namespace");
+
+    let namespace_check = read_attrs.namespace.map(|namespace| {
+        quote! {
+            if #reader_variable.options().check_namespaces && #tag_name_variable.namespace.as_deref() != Some(#namespace) {
+                return Err(ex_em_ell::errors::XmlReadError::InvalidNamespaceError {
+                    expected_namespace: #namespace.to_string(),
+                    actual_namespace: #tag_name_variable.namespace.clone(),
+                    position: Some(#reader_variable.position()),
+                    path: Some(#reader_variable.path().clone()),
+                });
+            }
+        }
+    });
 
     let (code, return_expression) =
-        generate_read(input, reader_variable, &tag_name_borrowed_variable);
+        generate_read(input, reader_variable, &tag_name_borrowed_variable, mode);
+    let next_event = mode.next_event();
+    let next_non_misc_event = mode.next_non_misc_event();
+    let attributes_type = mode.attributes_type();
 
     quote! {
-        #reader_variable
-                .next()
-                .map_err(ex_em_ell::xml_utils::to_xml_read_error(#tag_name))
-                .and_then(|event| match event {
-                    ex_em_ell::xml::reader::XmlEvent::StartDocument { .. } => Ok(()),
-                    unexpected => Err(ex_em_ell::xml_utils::unexpected_element_error(#tag_name, unexpected)),
-                })?;
-
-            let #tag_name_variable = #reader_variable
-                .next()
-                .map_err(ex_em_ell::xml_utils::to_xml_read_error(#tag_name))
-                .and_then(|event| match event {
-                    ex_em_ell::xml::reader::XmlEvent::StartElement {
-                        name,
-                        attributes,
-                        namespace,
-                    } if name.local_name == #tag_name => {
-    Ok(name)
-                    }
-                    unexpected => Err(ex_em_ell::xml_utils::unexpected_element_error(#tag_name, unexpected)),
-                })?;
-            let #tag_name_borrowed_variable = &#tag_name_variable;
+    #reader_variable
+            .#next_event()
+            .and_then(|event| match event {
+                ex_em_ell::events::XmlEvent::StartDocument { .. } => Ok(()),
+                unexpected => Err(ex_em_ell::xml_utils::unexpected_event_error(#reader_variable, #tag_name, unexpected)),
+            })?;
+
+        let (#tag_name_variable, #attributes_variable, #namespace_variable) = #next_non_misc_event(#reader_variable)
+            .and_then(|event| match event {
+                ex_em_ell::events::XmlEvent::StartElement {
+                    name,
+                    attributes,
+                    namespace,
+                } if name.local_name == #tag_name => {
+                    Ok((name, attributes, namespace))
+                }
+                unexpected => Err(ex_em_ell::xml_utils::unexpected_event_with_known_values_error(#reader_variable, #tag_name, vec![#tag_name.to_string()], unexpected)),
+            })?;
+        #namespace_check
+        let #tag_name_borrowed_variable = &#tag_name_variable;
+        let element_attributes: &[#attributes_type] = &#attributes_variable;
+        let element_namespace = &#namespace_variable;
 
-            #code
+        #code
 
-            #reader_variable
-                .next()
-                .map_err(ex_em_ell::xml_utils::to_xml_read_error(#tag_name))
-                .and_then(|event| match event {
-                    ex_em_ell::xml::reader::XmlEvent::EndDocument => Ok(()),
-                    unexpected => Err(ex_em_ell::xml_utils::unexpected_element_error(#tag_name, unexpected)),
-                })?;
+        #next_non_misc_event(#reader_variable)
+            .and_then(|event| match event {
+                ex_em_ell::events::XmlEvent::EndDocument => Ok(()),
+                unexpected => Err(ex_em_ell::xml_utils::unexpected_event_error(#reader_variable, #tag_name, unexpected)),
+            })?;
 
-            #return_expression
-        }
+        #return_expression
+    }
 }
 
 pub(crate) fn generate_read_xml_element(
     input: &DeriveInput,
     reader_variable: &Ident,
     tag_name_variable: &Ident,
+    mode: ReadMode,
 ) -> TokenStream {
-    let (code, return_expression) = generate_read(input, reader_variable, tag_name_variable);
+    let (code, return_expression) = generate_read(input, reader_variable, tag_name_variable, mode);
 
     quote! {
         #code
@@ -77,12 +163,45 @@ pub(crate) fn generate_read_xml_element(
     }
 }
 
+/// The declarations, state machine arms, missing field checks, required variables and struct
+/// fields generated for each field
+type FieldReaders = (
+    Vec<TokenStream>,
+    Vec<TokenStream>,
+    Vec<TokenStream>,
+    Vec<TokenStream>,
+    Vec<TokenStream>,
+);
+
 fn generate_read(
     input: &DeriveInput,
     reader_variable: &Ident,
     tag_name_variable: &Ident,
+    mode: ReadMode,
 ) -> (TokenStream, TokenStream) {
-    let (variable_declarations, state_machine, required_variables, struct_fields): (
+    if let Data::Enum(ref data) = input.data {
+        return generate_read_untagged(input, data, reader_variable, tag_name_variable, mode);
+    }
+
+    let container_attrs: ReadAttrs = input
+        .attrs
+        .iter()
+        .find_map(|attr| FromMeta::from_meta(&attr.meta).ok())
+        .unwrap_or_default();
+    let is_child_element = if crate::is_unqualified_form(
+        container_attrs.element_form_default.as_deref(),
+        input.ident.span(),
+    ) {
+        quote! { ex_em_ell::xml_utils::is_unqualified_child_element }
+    } else {
+        quote! { ex_em_ell::xml_utils::is_child_element }
+    };
+
+    let start_position_variable = format_ident!("_{}", "start_position");
+    let collected_errors_variable = format_ident!("_{}", "collected_errors");
+
+    let (variable_declarations, state_machine, missing_checks, required_variables, struct_fields): (
+        TokenStream,
         TokenStream,
         TokenStream,
         TokenStream,
@@ -90,53 +209,138 @@ fn generate_read(
     ) = match input.data {
         Data::Struct(ref data) => match data.fields {
             Fields::Named(ref fields) => {
+                let mut expected_elements = Vec::new();
                 let (
                     variable_declarations_recurse,
                     state_machine_arms_recurse,
+                    missing_checks_recurse,
                     required_variable_declarations_recurse,
                     struct_fields_recurse,
-                ): (
-                    Vec<TokenStream>,
-                    Vec<TokenStream>,
-                    Vec<TokenStream>,
-                    Vec<TokenStream>,
-                ) = itertools::multiunzip(fields.named.iter().map(|f| {
+                ): FieldReaders = itertools::multiunzip(fields.named.iter().map(|f| {
                     let name = &f
                         .ident
                         .as_ref()
                         .expect("Named field should have an identifier");
 
                     let variable = format_ident!("_{}", name);
+                    let invalid_variable = format_ident!("{}_invalid", variable);
 
                     let variable_type = &f.ty;
 
                     let read_attrs: ReadAttrs = f.attrs.iter().find_map(|attr| FromMeta::from_meta(&attr.meta).ok()).unwrap_or_default();
+                    crate::reject_field_namespace(read_attrs.namespace.as_deref(), f);
                     let field_tag_name = read_attrs.rename.unwrap_or_else(|| name.to_string().to_lower_camel_case());
 
-                    let variable_declaration = quote_spanned! { f.span() =>
-                                     let mut #variable : Option<#variable_type> = None;
-                    };
+                    let (variable_declaration, state_machine_arm) = if read_attrs.comments {
+                        let comment = mode.comment();
+                        let variable_declaration = quote_spanned! { f.span() =>
+                                         let mut #variable : #variable_type = Vec::new();
+                        };
 
-                    let state_machine_arm = quote_spanned! { f.span() =>
-                                                             ex_em_ell::xml::reader::XmlEvent::StartElement {
+                        let state_machine_arm = quote_spanned! { f.span() =>
+                                                             ex_em_ell::events::XmlEvent::Comment(comment) => {
+                                                                 #variable.push(#comment)
+                                                             }
+                        };
+
+                        (variable_declaration, state_machine_arm)
+                    } else if read_attrs.attribute {
+                        let attribute_reader = if read_attrs.list {
+                            quote! { ex_em_ell::xml_utils::read_xml_list_attribute }
+                        } else {
+                            mode.read_attribute()
+                        };
+
+                        // An `Option` attribute is never missing, it is `None` instead
+                        let value = if crate::option_inner_type(variable_type).is_some() {
+                            quote! { Some(#variable.flatten()) }
+                        } else {
+                            quote! { #variable.flatten() }
+                        };
+                        let variable_declaration = quote_spanned! { f.span() =>
+                                         let #variable = #reader_variable.recover(#attribute_reader(#reader_variable, element_attributes, #field_tag_name, #tag_name_variable))?;
+                                         let #invalid_variable = #variable.is_none();
+                                         let #variable : Option<#variable_type> = #value;
+                        };
+
+                        (variable_declaration, TokenStream::new())
+                    } else {
+                        expected_elements.push(field_tag_name.clone());
+                        let variable_declaration = quote_spanned! { f.span() =>
+                                         let mut #variable : Option<#variable_type> = None;
+                                         let mut #invalid_variable = false;
+                        };
+
+                        let state_machine_arm = if read_attrs.list {
+                            quote_spanned! { f.span() =>
+                                                             ex_em_ell::events::XmlEvent::StartElement {
+                                                                 name, ..
+                                                             } if #is_child_element(&name, #tag_name_variable, #field_tag_name) => {
+                                                                 let _depth = #reader_variable.depth();
+                                                                 let _value = ex_em_ell::xml_utils::read_xml_list_tag(#reader_variable, &name);
+                                                                 #variable = #reader_variable.recover_element(_value, _depth)?;
+                                                                 #invalid_variable = #variable.is_none();
+                                                             }
+                            }
+                        } else {
+                            let read_element = mode.read_element();
+                            quote_spanned! { f.span() =>
+                                                             ex_em_ell::events::XmlEvent::StartElement {
                                                                  name, attributes, namespace, ..
-                                                             } if name.local_name == #field_tag_name => {
-                                                                 #variable = Some(ex_em_ell::traits::FromXmlElement::from_xml_element(
+                                                             } if #is_child_element(&name, #tag_name_variable, #field_tag_name) => {
+                                                                 let _depth = #reader_variable.depth();
+                                                                 let _value = #read_element(
                                                                      #reader_variable,
                                                                      &name,
                                                                      &attributes,
                                                                      &namespace,
-                                                                 )?)
+                                                                 );
+                                                                 #variable = #reader_variable.recover_element(_value, _depth)?;
+                                                                 #invalid_variable = #variable.is_none();
                                                              }
+                            }
+                        };
+
+                        (variable_declaration, state_machine_arm)
                     };
 
                     let required_variable = format_ident!("{}_required", variable);
 
-                    let required_variable_declaration = quote_spanned! { f.span() =>
-                                                                         let #required_variable: #variable_type = #variable.ok_or_else(|| ex_em_ell::errors::XmlReadError::RequiredDataMissing {
-                                                                             required_field: #field_tag_name.to_string(),
-                                                                             element: #tag_name_variable.to_string(),
-                                                                         })?;
+                    let missing_error = quote_spanned! { f.span() =>
+                        ex_em_ell::errors::XmlReadError::RequiredDataMissing {
+                            required_field: #field_tag_name.to_string(),
+                            element: #tag_name_variable.to_string(),
+                            position: Some(#start_position_variable),
+                            path: Some(#reader_variable.path().clone()),
+                        }
+                    };
+
+                    let (missing_check, required_variable_declaration) = if read_attrs.comments {
+                        (TokenStream::new(), quote_spanned! { f.span() =>
+                                         let #required_variable: #variable_type = #variable;
+                        })
+                    } else if !read_attrs.attribute && !read_attrs.list {
+                        // Elements of some types, e.g. `Nillable`, have a value when they are missing
+                        let from_missing_element = mode.missing_element(variable_type);
+                        (quote_spanned! { f.span() =>
+                                         if #variable.is_none() && !#invalid_variable {
+                                             #variable = #from_missing_element;
+                                             if #variable.is_none() {
+                                                 #reader_variable.record_error(#missing_error)?;
+                                             }
+                                         }
+                        }, quote_spanned! { f.span() =>
+                                         let #required_variable: #variable_type = #variable.ok_or_else(|| #missing_error)?;
+                        })
+                    } else {
+                        // A field that was present but invalid has already been reported
+                        (quote_spanned! { f.span() =>
+                                         if #variable.is_none() && !#invalid_variable {
+                                             #reader_variable.record_error(#missing_error)?;
+                                         }
+                        }, quote_spanned! { f.span() =>
+                                         let #required_variable: #variable_type = #variable.ok_or_else(|| #missing_error)?;
+                        })
                     };
 
                     let struct_field = quote_spanned! { f.span() =>
@@ -146,6 +350,7 @@ fn generate_read(
                     (
                         variable_declaration,
                         state_machine_arm,
+                        missing_check,
                         required_variable_declaration,
                         struct_field,
                     )
@@ -156,23 +361,26 @@ fn generate_read(
                 let state_machine_arms_recurse: TokenStream =
                     state_machine_arms_recurse.into_iter().collect();
 
+                let next_event = mode.next_event();
                 let state_machine = quote! {
                     let mut got_end_tag = false;
                     while !got_end_tag {
                         let next_element = #reader_variable
-                            .next()
-                            .map_err(ex_em_ell::xml_utils::to_xml_read_error(#tag_name_variable.to_string()))?;
+                            .#next_event()?;
                         match next_element {
                             #state_machine_arms_recurse
-                            ex_em_ell::xml::reader::XmlEvent::EndElement { name } if &name == #tag_name_variable => {
+                            ex_em_ell::events::XmlEvent::EndElement { name } if &name == #tag_name_variable => {
                                 got_end_tag = true;
                             }
-                            unexpected => return Err(ex_em_ell::xml_utils::unexpected_element_error(#tag_name_variable.to_string(), unexpected)),
+                            event if ex_em_ell::xml_utils::is_misc_event(&event) => {}
+                            unexpected => ex_em_ell::xml_utils::skip_unexpected_event(#reader_variable, #tag_name_variable.to_string(), &[#(#expected_elements),*], unexpected)?,
                         }
                     }
 
                 };
 
+                let missing_checks: TokenStream = missing_checks_recurse.into_iter().collect();
+
                 let required_variables: TokenStream =
                     required_variable_declarations_recurse.into_iter().collect();
 
@@ -181,23 +389,34 @@ fn generate_read(
                 (
                     variable_declarations,
                     state_machine,
+                    missing_checks,
                     required_variables,
                     struct_fields,
                 )
             }
-            Fields::Unnamed(_) => unimplemented!(),
+            Fields::Unnamed(ref fields) => proc_macro_error::abort!(
+                fields,
+                "Tuple structs cannot be derived, implement `ex_em_ell::XmlSimpleType` for newtypes instead"
+            ),
             Fields::Unit => unimplemented!(),
         },
-        Data::Enum(_) => unimplemented!(),
+        Data::Enum(_) => unreachable!("Enums are read by generate_read_untagged"),
         Data::Union(_) => unimplemented!(),
     };
 
     (
         quote! {
+        let #start_position_variable = #reader_variable.position();
+        let #collected_errors_variable = #reader_variable.collected_error_count();
+
         #variable_declarations
 
+
         #state_machine
 
+        #missing_checks
+        #reader_variable.check_collected_errors(#collected_errors_variable)?;
+
         #required_variables
         },
         quote! {
@@ -208,8 +427,162 @@ fn generate_read(
     )
 }
 
+/// Read an `untagged` enum by reading the element as each variant in turn, rewinding after each
+/// one that fails, and reporting every variant's error if none of them can be read
+fn generate_read_untagged(
+    input: &DeriveInput,
+    data: &DataEnum,
+    reader_variable: &Ident,
+    tag_name_variable: &Ident,
+    mode: ReadMode,
+) -> (TokenStream, TokenStream) {
+    let read_attrs: ReadAttrs = input
+        .attrs
+        .iter()
+        .find_map(|attr| FromMeta::from_meta(&attr.meta).ok())
+        .unwrap_or_default();
+    if read_attrs.xsi_type {
+        return generate_read_xsi_type(data, reader_variable, tag_name_variable, mode);
+    }
+    if !read_attrs.untagged {
+        proc_macro_error::abort!(
+            input.ident,
+            "Only enums with `#[ex_em_ell(untagged)]` or `#[ex_em_ell(xsi_type)]` can be read"
+        );
+    }
+
+    let value_variable = format_ident!("_{}", "value");
+    let variant_errors_variable = format_ident!("_{}", "variant_errors");
+    let read_element = mode.read_element();
+
+    let variant_readers = data.variants.iter().map(|variant| {
+        let variant_name = &variant.ident;
+        let variant_display = variant_name.to_string();
+        match variant.fields {
+            Fields::Unnamed(ref fields) if fields.unnamed.len() == 1 => {}
+            _ => proc_macro_error::abort!(
+                variant,
+                "Variants of untagged enums must have exactly one unnamed field"
+            ),
+        }
+
+        quote_spanned! { variant.span() =>
+            match #reader_variable.attempt(|_reader| #read_element(_reader, #tag_name_variable, element_attributes, element_namespace)) {
+                Ok(value) => break 'variants Self::#variant_name(value),
+                Err(error) if !error.is_recoverable() => return Err(error),
+                Err(error) => #variant_errors_variable.push((#variant_display.to_string(), error)),
+            }
+        }
+    });
+
+    (
+        quote! {
+            let mut #variant_errors_variable = Vec::new();
+            let #value_variable: Self = 'variants: {
+                #(#variant_readers)*
+
+                return Err(ex_em_ell::errors::XmlReadError::NoMatchingVariant {
+                    errors: #variant_errors_variable,
+                    element: #tag_name_variable.to_string(),
+                    position: Some(#reader_variable.position()),
+                    path: Some(#reader_variable.path().clone()),
+                });
+            };
+        },
+        quote! {
+            Ok(#value_variable)
+        },
+    )
+}
+
+/// Read an `xsi_type` enum as the variant named by the element's `xsi:type` attribute
+///
+/// Each variant is the type named by its `rename`, or the variant name, in its `namespace`, if it
+/// has one.
+fn generate_read_xsi_type(
+    data: &DataEnum,
+    reader_variable: &Ident,
+    tag_name_variable: &Ident,
+    mode: ReadMode,
+) -> (TokenStream, TokenStream) {
+    let value_variable = format_ident!("_{}", "value");
+    let xsi_type_variable = format_ident!("_{}", "xsi_type");
+    let read_element = mode.read_element();
+
+    let (variant_readers, expected_types): (Vec<TokenStream>, Vec<String>) = data
+        .variants
+        .iter()
+        .map(|variant| {
+            let variant_name = &variant.ident;
+            match variant.fields {
+                Fields::Unnamed(ref fields) if fields.unnamed.len() == 1 => {}
+                _ => proc_macro_error::abort!(
+                    variant,
+                    "Variants of xsi_type enums must have exactly one unnamed field"
+                ),
+            }
+
+            let variant_attrs: ReadAttrs = variant
+                .attrs
+                .iter()
+                .find_map(|attr| FromMeta::from_meta(&attr.meta).ok())
+                .unwrap_or_default();
+            let type_name = variant_attrs
+                .rename
+                .unwrap_or_else(|| variant_name.to_string());
+            // A type without a namespace is written without a prefix, which would be in the
+            // default namespace if there is one, so it is matched on the missing prefix instead
+            let (namespace_pattern, expected_type) = match variant_attrs.namespace {
+                Some(namespace) => (
+                    quote! { Some(#namespace), _ },
+                    format!("{{{}}}{}", namespace, type_name),
+                ),
+                None => (quote! { _, true }, type_name.clone()),
+            };
+
+            (
+                quote_spanned! { variant.span() =>
+                    (#namespace_pattern, #type_name) => Self::#variant_name(#read_element(#reader_variable, #tag_name_variable, element_attributes, element_namespace)?),
+                },
+                expected_type,
+            )
+        })
+        .unzip();
+
+    (
+        quote! {
+            let #xsi_type_variable = ex_em_ell::xml_utils::read_xsi_type(#reader_variable, element_attributes, element_namespace, #tag_name_variable)?;
+            let #value_variable: Self = match (#xsi_type_variable.namespace.as_deref(), #xsi_type_variable.prefix.is_none(), #xsi_type_variable.local_name.as_str()) {
+                #(#variant_readers)*
+                _ => return Err(ex_em_ell::xml_utils::unknown_xsi_type_error(#reader_variable, #tag_name_variable, &#xsi_type_variable, &[#(#expected_types),*])),
+            };
+        },
+        quote! {
+            Ok(#value_variable)
+        },
+    )
+}
+
 #[derive(Debug, Default, FromMeta)]
 struct ReadAttrs {
     #[darling(default)]
     rename: Option<String>,
+    #[darling(default)]
+    attribute: bool,
+    #[darling(default)]
+    list: bool,
+    #[darling(default)]
+    comments: bool,
+    #[darling(default)]
+    namespace: Option<String>,
+    #[darling(default)]
+    untagged: bool,
+    #[darling(default)]
+    xsi_type: bool,
+    #[darling(default)]
+    element_form_default: Option<String>,
+    /// Only used to write an `xsi:type`, but accepted so that the rest of the attributes parse
+    #[darling(default)]
+    #[allow(dead_code)]
+    prefix: Option<String>,
 }
@@ -3,7 +3,7 @@ use heck::ToLowerCamelCase;
 use proc_macro2::{Ident, TokenStream};
 use quote::{format_ident, quote, quote_spanned};
 use syn::spanned::Spanned;
-use syn::{Data, DeriveInput, Fields};
+use syn::{Data, DataEnum, DeriveInput, Fields};
 
 pub(crate) fn generate_write_xml_document(
     input: &DeriveInput,
@@ -20,7 +20,25 @@ pub(crate) fn generate_write_xml_document(
 
     let tag_name_variable = format_ident!("_{}", "tag_name");
 
-    let writer = generate_write_xml_element(input, &writer_variable, &tag_name_variable);
+    // Children without a prefix are in the default namespace, so the namespace of a document
+    // whose children are unqualified is declared with a prefix instead
+    let is_unqualified = crate::is_unqualified_form(
+        write_attrs.element_form_default.as_deref(),
+        input.ident.span(),
+    );
+    let (tag_name, namespace) = match (write_attrs.namespace, write_attrs.prefix) {
+        (Some(namespace), Some(prefix)) if is_unqualified => (
+            format!("{}:{}", prefix, tag_name),
+            Some(DocumentNamespace::Prefixed(prefix, namespace)),
+        ),
+        (Some(_), None) if is_unqualified => proc_macro_error::abort!(
+            input.ident,
+            "Documents with `element_form_default = \"unqualified\"` need a `prefix` for their `namespace`"
+        ),
+        (namespace, _) => (tag_name, namespace.map(DocumentNamespace::Default)),
+    };
+
+    let writer = generate_write_xml_element(input, writer_variable, &tag_name_variable, namespace);
 
     quote! {
         let #tag_name_variable = #tag_name;
@@ -29,50 +47,239 @@ pub(crate) fn generate_write_xml_document(
     }
 }
 
+/// The namespace of a document's root element, declared on it as the default namespace or with
+/// a prefix
+pub(crate) enum DocumentNamespace {
+    Default(String),
+    Prefixed(String, String),
+}
+
 pub(crate) fn generate_write_xml_element(
     input: &DeriveInput,
     writer_variable: &Ident,
     tag_name_variable: &Ident,
+    namespace: Option<DocumentNamespace>,
 ) -> TokenStream {
-    let field_writers: TokenStream = match input.data {
-        Data::Struct(ref data) => match data.fields {
-            Fields::Named(ref fields) => {
-                let recurse = fields.named.iter().map(|f| {
-                    let name = &f
-                        .ident
-                        .as_ref()
-                        .expect("Named field should have an identifier");
-
-                    let write_attrs: WriteAttrs = f.attrs.iter().find_map(|attr| FromMeta::from_meta(&attr.meta).ok()).unwrap_or_default();
-                    let field_tag_name = write_attrs.rename.unwrap_or_else(|| name.to_string().to_lower_camel_case());
-
-                    quote_spanned! { f.span() =>
-                       ex_em_ell::traits::ToXmlElement::to_xml_element(&self.#name, #writer_variable, #field_tag_name)?;
-                    }
-                });
-                quote! {
-                    #(#recurse)*
+    if let Data::Enum(ref data) = input.data {
+        return generate_write_untagged(input, data, writer_variable, tag_name_variable);
+    }
+
+    let (attribute_texts, attributes, field_writers): (TokenStream, TokenStream, TokenStream) =
+        match input.data {
+            Data::Struct(ref data) => match data.fields {
+                Fields::Named(ref fields) => {
+                    let (attribute_texts, attributes, field_writers): (
+                        Vec<TokenStream>,
+                        Vec<TokenStream>,
+                        Vec<TokenStream>,
+                    ) = itertools::multiunzip(fields.named.iter().map(|f| {
+                        let name = &f
+                            .ident
+                            .as_ref()
+                            .expect("Named field should have an identifier");
+
+                        let write_attrs: WriteAttrs = f.attrs.iter().find_map(|attr| FromMeta::from_meta(&attr.meta).ok()).unwrap_or_default();
+                        crate::reject_field_namespace(write_attrs.namespace.as_deref(), f);
+                        let field_tag_name = write_attrs.rename.unwrap_or_else(|| name.to_string().to_lower_camel_case());
+
+                        if write_attrs.comments {
+                            (
+                                TokenStream::new(),
+                                TokenStream::new(),
+                                quote_spanned! { f.span() =>
+                                    for comment in &self.#name {
+                                        ex_em_ell::xml_utils::write_event(#writer_variable, ex_em_ell::events::WriteEvent::comment(comment), #tag_name_variable)?;
+                                    }
+                                },
+                            )
+                        } else if write_attrs.attribute {
+                            let text_variable = format_ident!("_{}_text", name);
+
+                            let to_text = if write_attrs.list {
+                                quote! { ex_em_ell::xml_utils::to_xml_list_text }
+                            } else {
+                                quote! { ex_em_ell::traits::XmlSimpleType::to_xml_text }
+                            };
+                            if crate::option_inner_type(&f.ty).is_some() {
+                                // An `Option` attribute is only written when it is `Some`
+                                (
+                                    quote_spanned! { f.span() =>
+                                        let #text_variable = self.#name.as_ref().map(|value| #to_text(value));
+                                    },
+                                    quote_spanned! { f.span() =>
+                                        .optional_attr(#field_tag_name, #text_variable.as_deref())
+                                    },
+                                    TokenStream::new(),
+                                )
+                            } else {
+                                (
+                                    quote_spanned! { f.span() =>
+                                        let #text_variable = #to_text(&self.#name);
+                                    },
+                                    quote_spanned! { f.span() =>
+                                        .attr(#field_tag_name, &#text_variable)
+                                    },
+                                    TokenStream::new(),
+                                )
+                            }
+                        } else {
+                            (
+                                TokenStream::new(),
+                                TokenStream::new(),
+                                if write_attrs.list {
+                                    quote_spanned! { f.span() =>
+                                       ex_em_ell::xml_utils::write_xml_list_tag(#writer_variable, #field_tag_name, &self.#name)?;
+                                    }
+                                } else {
+                                    quote_spanned! { f.span() =>
+                                       ex_em_ell::traits::ToXmlElement::to_xml_element(&self.#name, #writer_variable, #field_tag_name)?;
+                                    }
+                                },
+                            )
+                        }
+                    }));
+                    (
+                        attribute_texts.into_iter().collect(),
+                        attributes.into_iter().collect(),
+                        field_writers.into_iter().collect(),
+                    )
                 }
-            }
-            Fields::Unnamed(_) => unimplemented!(),
-            Fields::Unit => unimplemented!(),
-        },
-        Data::Enum(_) => unimplemented!(),
-        Data::Union(_) => unimplemented!(),
-    };
+                Fields::Unnamed(ref fields) => proc_macro_error::abort!(
+                fields,
+                "Tuple structs cannot be derived, implement `ex_em_ell::XmlSimpleType` for newtypes instead"
+            ),
+                Fields::Unit => unimplemented!(),
+            },
+            Data::Enum(_) => unreachable!("Enums are written by generate_write_untagged"),
+            Data::Union(_) => unimplemented!(),
+        };
+
+    let default_namespace = namespace.map(|namespace| match namespace {
+        DocumentNamespace::Default(namespace) => quote! { .default_ns(#namespace) },
+        DocumentNamespace::Prefixed(prefix, namespace) => quote! { .ns(#prefix, #namespace) },
+    });
 
     quote! {
-        #writer_variable.write(ex_em_ell::xml::writer::XmlEvent::start_element(#tag_name_variable)).map_err(ex_em_ell::xml_utils::to_xml_write_error(#tag_name_variable))?;
+        #attribute_texts
+
+
+        ex_em_ell::xml_utils::write_event(#writer_variable, ex_em_ell::events::WriteEvent::start_element(#tag_name_variable)#default_namespace #attributes, #tag_name_variable)?;
 
         #field_writers
 
-        #writer_variable.write(ex_em_ell::xml::writer::XmlEvent::end_element()).map_err(ex_em_ell::xml_utils::to_xml_write_error(#tag_name_variable))?;
+        ex_em_ell::xml_utils::write_event(#writer_variable, ex_em_ell::events::WriteEvent::end_element(), #tag_name_variable)?;
 
     }
 }
 
+/// Write an `untagged` enum as whichever variant it holds
+fn generate_write_untagged(
+    input: &DeriveInput,
+    data: &DataEnum,
+    writer_variable: &Ident,
+    tag_name_variable: &Ident,
+) -> TokenStream {
+    let write_attrs: WriteAttrs = input
+        .attrs
+        .iter()
+        .find_map(|attr| FromMeta::from_meta(&attr.meta).ok())
+        .unwrap_or_default();
+    if write_attrs.xsi_type {
+        return generate_write_xsi_type(data, writer_variable, tag_name_variable);
+    }
+    if !write_attrs.untagged {
+        proc_macro_error::abort!(
+            input.ident,
+            "Only enums with `#[ex_em_ell(untagged)]` or `#[ex_em_ell(xsi_type)]` can be written"
+        );
+    }
+
+    let variant_writers = data.variants.iter().map(|variant| {
+        let variant_name = &variant.ident;
+        match variant.fields {
+            Fields::Unnamed(ref fields) if fields.unnamed.len() == 1 => {}
+            _ => proc_macro_error::abort!(
+                variant,
+                "Variants of untagged enums must have exactly one unnamed field"
+            ),
+        }
+
+        quote_spanned! { variant.span() =>
+            Self::#variant_name(value) => ex_em_ell::traits::ToXmlElement::to_xml_element(value, #writer_variable, #tag_name_variable)?,
+        }
+    });
+
+    quote! {
+        match self {
+            #(#variant_writers)*
+        }
+    }
+}
+
+/// Write an `xsi_type` enum as whichever variant it holds, with an `xsi:type` attribute naming
+/// the variant's type
+fn generate_write_xsi_type(
+    data: &DataEnum,
+    writer_variable: &Ident,
+    tag_name_variable: &Ident,
+) -> TokenStream {
+    let variant_writers = data.variants.iter().map(|variant| {
+        let variant_name = &variant.ident;
+        match variant.fields {
+            Fields::Unnamed(ref fields) if fields.unnamed.len() == 1 => {}
+            _ => proc_macro_error::abort!(
+                variant,
+                "Variants of xsi_type enums must have exactly one unnamed field"
+            ),
+        }
+
+        let variant_attrs: WriteAttrs = variant
+            .attrs
+            .iter()
+            .find_map(|attr| FromMeta::from_meta(&attr.meta).ok())
+            .unwrap_or_default();
+        let type_name = variant_attrs
+            .rename
+            .unwrap_or_else(|| variant_name.to_string());
+        let type_namespace = match (variant_attrs.namespace, variant_attrs.prefix) {
+            (Some(namespace), Some(prefix)) => quote! { Some((#prefix, #namespace)) },
+            (Some(_), None) => proc_macro_error::abort!(
+                variant,
+                "Variants with a `namespace` need a `prefix` to write their `xsi:type` with"
+            ),
+            (None, _) => quote! { None },
+        };
+
+        quote_spanned! { variant.span() =>
+            Self::#variant_name(value) => ex_em_ell::xml_utils::write_with_xsi_type(#writer_variable, value, #tag_name_variable, #type_name, #type_namespace)?,
+        }
+    });
+
+    quote! {
+        match self {
+            #(#variant_writers)*
+        }
+    }
+}
+
 #[derive(Debug, Default, FromMeta)]
 struct WriteAttrs {
     #[darling(default)]
     rename: Option<String>,
+    #[darling(default)]
+    attribute: bool,
+    #[darling(default)]
+    list: bool,
+    #[darling(default)]
+    comments: bool,
+    #[darling(default)]
+    namespace: Option<String>,
+    #[darling(default)]
+    untagged: bool,
+    #[darling(default)]
+    xsi_type: bool,
+    #[darling(default)]
+    prefix: Option<String>,
+    #[darling(default)]
+    element_form_default: Option<String>,
 }
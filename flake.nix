@@ -81,6 +81,19 @@
             inherit cargoArtifacts;
           });
 
+          # The quick-xml backend and the async functions are behind features, which the checks
+          # above leave out
+          clippy-all-features = craneLib.cargoClippy (commonArgs // {
+            inherit cargoArtifacts;
+            cargoExtraArgs = "--locked --all-features";
+            cargoClippyExtraArgs = "--all-targets -- --deny warnings";
+          });
+
+          test-all-features = craneLib.cargoTest (commonArgs // {
+            inherit cargoArtifacts;
+            cargoExtraArgs = "--locked --all-features";
+          });
+
           doc = craneLib.cargoDoc (commonArgs // {
             inherit cargoArtifacts;
           });